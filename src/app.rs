@@ -1,15 +1,82 @@
-use crate::data_processing::{Name, NameSegment, generate_data, SegmentKind};
-use crate::name_gen::{GeneratedName, NameGenOptions, generate_names_from_parts};
+use crate::config::{CONFIG_FILE, GeneratorConfig};
+use crate::constraints::Bounds;
+use crate::culture::{CulturePack, ReservedPolicy, ReservedSegment, available_packs};
+use crate::data_processing::{
+    BlendWeight, Corpus, CorpusFilter, Name, NameSegment, SegmentKind, blend_corpora,
+    corpus_labels, write_data,
+};
+use crate::deny_list::{DEFAULT_DENY_LIST, entry_count};
+use crate::family::{Family, FamilyKind, FamilyOptions, generate_family};
+use crate::gender::{DEFAULT_FOLDS, DEFAULT_GENDER_TOLERANCE, GenderClassifier, GenderReport};
+use crate::joiners::JoinerStyle;
+use crate::name_gen::{
+    CorpusModels, GeneratedName, NameGenOptions, Sampling, generate_names_from_parts,
+};
+use crate::variants::{Blend, Variants, blend_names, generate_variants};
+use std::mem::discriminant;
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct NameApp {
+    pack: CulturePack,
+    /// File the pack was selected from, where its generator settings are saved.
+    pack_path: Option<String>,
+    corpora: Vec<Corpus>,
+    presets: Vec<(String, NameGenOptions)>,
     names: Vec<Name>,
     syllables: Vec<NameSegment>,
     parts: Vec<NameSegment>,
+    /// Tribes and tags of the whole corpus, offered as filter choices.
+    labels: Vec<String>,
+    /// Read from the pack or `generator.toml` on start instead of being persisted.
+    #[serde(skip)]
+    config: GeneratorConfig,
+    #[serde(skip)]
+    active_filter: CorpusFilter,
+    /// Weights the corpora were last mixed with, the models are learned from that mix.
+    #[serde(skip)]
+    active_blend: Vec<BlendWeight>,
+    #[serde(skip)]
+    corpus_notice: Option<String>,
+    #[serde(skip)]
+    new_corpus: (String, String),
+    #[serde(skip)]
+    preset_name: String,
     #[serde(skip)]
     generated: Vec<(GeneratedName, bool)>,
+    /// Sample batch shown in the advanced settings.
+    #[serde(skip)]
+    preview: Vec<GeneratedName>,
+    #[serde(skip)]
+    family_options: FamilyOptions,
+    #[serde(skip)]
+    family: Option<Family>,
+    #[serde(skip)]
+    variant_seed: String,
+    #[serde(skip)]
+    variants: Option<Variants>,
+    /// Comma separated names to blend.
+    #[serde(skip)]
+    blend_input: String,
+    #[serde(skip)]
+    name_blend: Option<Blend>,
+    /// Learned from the blended corpus along with it.
+    #[serde(skip)]
+    models: CorpusModels,
+    /// Cross-validated accuracy of the gender classifier on the blended corpus.
+    #[serde(skip)]
+    gender_report: Option<GenderReport>,
+    #[serde(skip)]
+    preview_stale: bool,
+    /// Why the last generation came up short.
+    #[serde(skip)]
+    generation_notice: Option<String>,
+    #[serde(skip)]
+    new_reserved: String,
+    /// Text fields for adding required and forbidden texts.
+    #[serde(skip)]
+    new_texts: [String; 2],
     #[serde(skip)]
     name_gen_settings: NameGenOptions,
     #[serde(skip)]
@@ -19,16 +86,100 @@ pub struct NameApp {
 impl Default for NameApp {
     fn default() -> Self {
         Self {
+            pack: CulturePack::default(),
+            pack_path: None,
+            corpora: vec![],
+            presets: vec![],
             names: vec![],
             syllables: vec![],
             parts: vec![],
+            labels: vec![],
+            config: GeneratorConfig::default(),
+            active_filter: CorpusFilter::default(),
+            active_blend: vec![],
+            corpus_notice: None,
+            new_corpus: (String::new(), String::new()),
+            preset_name: String::new(),
             generated: vec![],
+            preview: vec![],
+            family_options: FamilyOptions::default(),
+            family: None,
+            variant_seed: String::new(),
+            variants: None,
+            blend_input: String::new(),
+            name_blend: None,
+            models: CorpusModels::default(),
+            gender_report: None,
+            preview_stale: true,
+            generation_notice: None,
+            new_reserved: String::new(),
+            new_texts: Default::default(),
             name_gen_settings: NameGenOptions::default(),
             selected_label: None,
         }
     }
 }
 
+/// Number of names in the advanced settings preview.
+const PREVIEW_AMOUNT: usize = 8;
+
+/// Colors telling apart the parents of blended names.
+const PARENT_COLORS: [egui::Color32; 4] = [
+    egui::Color32::LIGHT_BLUE,
+    egui::Color32::GOLD,
+    egui::Color32::LIGHT_GREEN,
+    egui::Color32::LIGHT_RED,
+];
+
+/// A checkbox that enables the value and a field to edit it.
+fn optional_value_ui(ui: &mut egui::Ui, text: &str, value: &mut Option<usize>, default: usize) {
+    let mut enabled = value.is_some();
+    ui.checkbox(&mut enabled, text);
+    if enabled {
+        ui.add(egui::DragValue::new(value.get_or_insert(default)).range(0..=30));
+    } else {
+        *value = None;
+    }
+}
+
+fn bounds_ui(ui: &mut egui::Ui, text: &str, bounds: &mut Bounds) {
+    ui.horizontal(|ui| {
+        ui.label(text);
+        optional_value_ui(ui, "at least", &mut bounds.min, 1);
+        optional_value_ui(ui, "at most", &mut bounds.max, 1);
+    });
+}
+
+/// Shows the texts as buttons that remove them and a field to add more.
+fn text_list_ui(ui: &mut egui::Ui, text: &str, texts: &mut Vec<String>, new_text: &mut String) {
+    ui.horizontal_wrapped(|ui| {
+        ui.label(text);
+        texts.retain(|t| !ui.button(format!("{t} ✖")).clicked());
+        ui.add(egui::TextEdit::singleline(new_text).desired_width(80.));
+        if ui.button("Add").clicked() && !new_text.trim().is_empty() {
+            texts.push(std::mem::take(new_text).trim().to_owned());
+        }
+    });
+}
+
+fn pattern_ui(ui: &mut egui::Ui, text: &str, pattern: &mut Option<String>) {
+    ui.horizontal(|ui| {
+        ui.label(text);
+        let mut edited = pattern.clone().unwrap_or_default();
+        ui.text_edit_singleline(&mut edited);
+        *pattern = (!edited.is_empty()).then_some(edited);
+    });
+}
+
+fn policy_text(policy: ReservedPolicy) -> &'static str {
+    match policy {
+        ReservedPolicy::Forbid => "Forbid",
+        ReservedPolicy::ForbidStart => "Not at the start",
+        ReservedPolicy::ForbidEnd => "Not at the end",
+        ReservedPolicy::DownWeight(_) => "Down-weight",
+    }
+}
+
 fn gender_text(gender_val: f32) -> String {
     match gender_val {
         ..0.05 => "female",
@@ -42,6 +193,40 @@ fn gender_text(gender_val: f32) -> String {
     .to_string()
 }
 
+fn label_toggles(ui: &mut egui::Ui, text: &str, labels: &[String], selected: &mut Vec<String>) {
+    ui.horizontal_wrapped(|ui| {
+        ui.label(text);
+        for label in labels {
+            let mut on = selected.contains(label);
+            if ui.toggle_value(&mut on, label).changed() {
+                if on {
+                    selected.push(label.clone());
+                } else {
+                    selected.retain(|l| l != label);
+                }
+            }
+        }
+    });
+}
+
+/// The corpus names a segment was taken from, linked if the pack has a name link.
+fn derived_names_ui(ui: &mut egui::Ui, segment: &NameSegment, name_link: Option<&str>) {
+    ui.label(format!("Derived from ({}):", segment.corpus));
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for name in &segment.derived_names {
+            ui.horizontal(|ui| {
+                let Some(link) = name_link else {
+                    ui.label(name);
+                    return;
+                };
+                ui.label(format!("{name} - ("));
+                ui.hyperlink_to("link", link.replace("{}", &urlencoding::encode(name)));
+                ui.label(")");
+            });
+        }
+    });
+}
+
 impl NameApp {
     /// Called once before the first frame.
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
@@ -50,20 +235,720 @@ impl NameApp {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default()
+        let mut app = if let Some(storage) = cc.storage {
+            let mut app: Self = eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+            // The models learned from the corpora are not persisted.
+            app.blend();
+            app
         } else {
             let mut r: NameApp = Default::default();
             r.load_from_files();
             r
-        }
+        };
+        app.load_config();
+        app
     }
 
     fn load_from_files(&mut self) {
-        let (s, p, n) = generate_data();
-        self.syllables = s.iter().map(|o| o.into()).collect();
-        self.parts = p.iter().map(|p| p.into()).collect();
-        self.names = n;
+        let filter = self.name_gen_settings.filter.clone();
+        let mut sources = self
+            .corpora
+            .iter()
+            .map(|c| (c.label.clone(), c.path.clone()))
+            .collect::<Vec<_>>();
+        if sources.is_empty() {
+            sources.push((self.pack.name.clone(), self.pack.corpus.clone()));
+        }
+        let mut notices = vec![];
+        self.corpora.clear();
+        for (label, path) in sources {
+            match Corpus::load(&label, &path, &filter) {
+                Ok((corpus, fell_back)) => {
+                    if fell_back {
+                        notices.push(format!(
+                            "No names in {label} match the corpus filter, using all of them."
+                        ));
+                    }
+                    self.corpora.push(corpus);
+                }
+                Err(e) => notices.push(format!("Could not load {label} ({path}): {e}")),
+            }
+        }
+        if filter.is_empty() {
+            let names = self.corpora.iter().flat_map(|c| c.names.iter().cloned());
+            self.labels = corpus_labels(&names.collect::<Vec<_>>());
+            if let Some(Err(e)) = self.corpora.first().map(write_data) {
+                notices.push(format!("Could not write statistics: {e}"));
+            }
+        }
+        self.corpus_notice = (!notices.is_empty()).then(|| notices.join("\n"));
+        self.active_filter = filter;
+        self.blend();
+    }
+
+    /// Uses the generator config of the pack, or `generator.toml` if the pack has none.
+    fn load_config(&mut self) {
+        self.preview_stale = true;
+        if let Some(config) = &self.pack.generator {
+            self.config = config.clone();
+            return;
+        }
+        match GeneratorConfig::load_default() {
+            Ok(config) => self.config = config,
+            Err(e) => {
+                self.config = GeneratorConfig::default();
+                self.corpus_notice = Some(format!("{e}, using the default settings."));
+            }
+        }
+    }
+
+    fn generate(&mut self, settings: &NameGenOptions) -> Vec<GeneratedName> {
+        let generation = generate_names_from_parts(
+            &self.parts,
+            &self.syllables,
+            &self.models,
+            settings,
+            &self.pack,
+            &self.config,
+        );
+        match generation {
+            Ok(generation) => {
+                self.generation_notice = generation.report();
+                generation.names
+            }
+            Err(e) => {
+                self.generation_notice = Some(e.to_string());
+                vec![]
+            }
+        }
+    }
+
+    /// Reloads the corpora if the filter changed or mixes them again if the weights did,
+    /// so the models are only learned again when the corpus they learn from changes.
+    fn update_corpus(&mut self) {
+        if self.name_gen_settings.filter != self.active_filter {
+            self.load_from_files();
+        } else if self.name_gen_settings.blend != self.active_blend {
+            self.blend();
+        }
+    }
+
+    fn generate_family(&mut self) {
+        self.update_corpus();
+        let family = generate_family(
+            &self.parts,
+            &self.syllables,
+            &self.models,
+            &self.name_gen_settings,
+            &self.pack,
+            &self.config,
+            &self.family_options,
+        );
+        match family {
+            Ok(family) => {
+                self.generation_notice = family.report.clone();
+                self.family = Some(family);
+            }
+            Err(e) => self.generation_notice = Some(e.to_string()),
+        }
+    }
+
+    fn family_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let kind = &mut self.family_options.kind;
+            egui::ComboBox::from_label("Kind")
+                .selected_text(kind.text())
+                .show_ui(ui, |ui| {
+                    for choice in FamilyKind::ALL {
+                        ui.selectable_value(kind, choice, choice.text());
+                    }
+                });
+            ui.add(egui::Slider::new(&mut self.family_options.size, 2..=12).text("Members"));
+            if ui.button("Generate family").clicked() {
+                self.generate_family();
+            }
+        });
+        let Some(family) = &self.family else {
+            return;
+        };
+        for member in &family.members {
+            ui.horizontal(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.;
+                for (text, shared) in member.segments() {
+                    if shared {
+                        let color = ui.visuals().warn_fg_color;
+                        ui.label(egui::RichText::new(text).strong().color(color));
+                    } else {
+                        ui.label(text);
+                    }
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Copy as text").clicked() {
+                ui.ctx().copy_text(family.to_string());
+            }
+            if ui.button("Copy as JSON").clicked()
+                && let Ok(json) = family.to_json()
+            {
+                ui.ctx().copy_text(json);
+            }
+        });
+    }
+
+    /// Speaks a name into a WAV file in the working directory.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_wav(&mut self, name: &GeneratedName) {
+        let path = crate::speech::wav_file_name(&name.to_string());
+        let pronunciation = self.models.pronouncer.pronounce(name);
+        let written = crate::speech::write_wav(&pronunciation, std::path::Path::new(&path));
+        self.generation_notice = Some(match written {
+            Ok(()) => format!("Saved {path}"),
+            Err(e) => format!("Could not save {path}: {e}"),
+        });
+    }
+
+    fn generate_variants(&mut self) {
+        let variants = generate_variants(
+            &self.variant_seed,
+            &self.parts,
+            &self.syllables,
+            &self.name_gen_settings,
+            &self.pack,
+            &self.config,
+        );
+        match variants {
+            Ok(variants) => {
+                self.generation_notice = None;
+                self.variants = Some(variants);
+            }
+            Err(e) => self.generation_notice = Some(e.to_string()),
+        }
+    }
+
+    fn variants_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Seed name");
+            ui.text_edit_singleline(&mut self.variant_seed);
+            if ui.button("Generate variants").clicked() {
+                self.generate_variants();
+            }
+        });
+        let Some(variants) = &self.variants else {
+            return;
+        };
+        ui.horizontal_top(|ui| {
+            ui.vertical(|ui| {
+                ui.strong(variants.original.to_string());
+                let segments = variants.original.elements.iter().map(|e| e.str.as_str());
+                ui.label(segments.collect::<Vec<_>>().join(" · "));
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                for variant in &variants.variants {
+                    ui.label(variant.name.to_string())
+                        .on_hover_text(variant.mutation.text());
+                }
+            });
+        });
+    }
+
+    fn generate_blend(&mut self) {
+        let names = self.blend_input.split(',').map(str::trim).collect::<Vec<_>>();
+        let blend = blend_names(
+            &names,
+            &self.parts,
+            &self.syllables,
+            &self.name_gen_settings,
+            &self.config,
+        );
+        match blend {
+            Ok(blend) => {
+                self.generation_notice = None;
+                self.name_blend = Some(blend);
+            }
+            Err(e) => self.generation_notice = Some(e.to_string()),
+        }
+    }
+
+    fn blend_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Names");
+            ui.text_edit_singleline(&mut self.blend_input)
+                .on_hover_text("Two or more names, separated by commas");
+            if ui.button("Blend").clicked() {
+                self.generate_blend();
+            }
+        });
+        let Some(blend) = &self.name_blend else {
+            return;
+        };
+        let color = |parent: usize| {
+            let color = PARENT_COLORS.get(parent % PARENT_COLORS.len());
+            color.copied().unwrap_or_default()
+        };
+        ui.horizontal_wrapped(|ui| {
+            for (i, parent) in blend.parents.iter().enumerate() {
+                ui.label(egui::RichText::new(parent.to_string()).strong().color(color(i)));
+            }
+        });
+        for blended in &blend.blends {
+            ui.horizontal(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.;
+                for (text, parent) in blended.contributions() {
+                    let parent_name = blend.parents.get(parent).map(ToString::to_string);
+                    ui.label(egui::RichText::new(text).color(color(parent)))
+                        .on_hover_text(parent_name.unwrap_or_default());
+                }
+                ui.label(format!("  {}", blended.name));
+            });
+        }
+    }
+
+    fn diversity_ui(&mut self, ui: &mut egui::Ui) {
+        let diversity = &mut self.name_gen_settings.diversity;
+        ui.checkbox(&mut diversity.unique, "No duplicate names");
+        ui.horizontal(|ui| {
+            let reuse = &mut diversity.max_part_reuse;
+            optional_value_ui(ui, "Reuses of a segment", reuse, 1);
+        });
+        ui.horizontal(|ui| {
+            let distance = &mut diversity.min_distance;
+            optional_value_ui(ui, "Letters that differ between names", distance, 2);
+        });
+    }
+
+    fn constraints_ui(&mut self, ui: &mut egui::Ui) {
+        let constraints = &mut self.name_gen_settings.constraints;
+        ui.horizontal(|ui| {
+            ui.label("Starts with");
+            ui.add(egui::TextEdit::singleline(&mut constraints.initial).desired_width(60.));
+        });
+        bounds_ui(ui, "Syllables", &mut constraints.syllables);
+        bounds_ui(ui, "Letters", &mut constraints.letters);
+        let [required, forbidden] = &mut self.new_texts;
+        text_list_ui(ui, "Contains", &mut constraints.required, required);
+        text_list_ui(ui, "Never contains", &mut constraints.forbidden, forbidden);
+        pattern_ui(ui, "Matches regex", &mut constraints.include);
+        pattern_ui(ui, "Does not match regex", &mut constraints.exclude);
+    }
+
+    fn reserved_ui(&mut self, ui: &mut egui::Ui) {
+        let mut removed = None;
+        for (i, reserved) in self.pack.reserved.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut reserved.segment).desired_width(60.));
+                let policy = &mut reserved.policy;
+                egui::ComboBox::from_id_salt(("reserved policy", i))
+                    .selected_text(policy_text(*policy))
+                    .show_ui(ui, |ui| {
+                        for choice in [
+                            ReservedPolicy::Forbid,
+                            ReservedPolicy::ForbidStart,
+                            ReservedPolicy::ForbidEnd,
+                            ReservedPolicy::DownWeight(0.2),
+                        ] {
+                            let current = discriminant(policy) == discriminant(&choice);
+                            if ui.selectable_label(current, policy_text(choice)).clicked()
+                                && !current
+                            {
+                                *policy = choice;
+                            }
+                        }
+                    });
+                if let ReservedPolicy::DownWeight(factor) = policy {
+                    ui.add(egui::Slider::new(factor, 0.0..=1.0).text("factor"));
+                }
+                if ui.button("Remove").clicked() {
+                    removed = Some(i);
+                }
+            });
+        }
+        if let Some(i) = removed {
+            self.pack.reserved.remove(i);
+        }
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.new_reserved).desired_width(60.));
+            if ui.button("Add").clicked() && !self.new_reserved.trim().is_empty() {
+                self.pack.reserved.push(ReservedSegment {
+                    segment: std::mem::take(&mut self.new_reserved).trim().to_owned(),
+                    policy: ReservedPolicy::Forbid,
+                });
+            }
+        });
+    }
+
+    fn deny_list_ui(&mut self, ui: &mut egui::Ui) {
+        let deny_list = &mut self.name_gen_settings.deny_list;
+        ui.checkbox(
+            &mut deny_list.use_default,
+            format!(
+                "Use the bundled list ({} entries)",
+                entry_count(DEFAULT_DENY_LIST)
+            ),
+        );
+        ui.label("Own entries, one per line: text, word:text or regex:expression");
+        ui.add(
+            egui::TextEdit::multiline(&mut deny_list.custom)
+                .hint_text("word:example")
+                .desired_rows(4),
+        );
+    }
+
+    fn refresh_preview(&mut self) {
+        if self.parts.is_empty() {
+            return;
+        }
+        self.preview_stale = false;
+        match self.config.validate() {
+            Ok(()) => {
+                let settings = NameGenOptions {
+                    amount: PREVIEW_AMOUNT,
+                    ..self.name_gen_settings.clone()
+                };
+                self.preview = self.generate(&settings);
+            }
+            Err(e) => self.corpus_notice = Some(e.to_string()),
+        }
+    }
+
+    fn advanced_ui(&mut self, ui: &mut egui::Ui) {
+        let config = &mut self.config;
+        let mut changed = false;
+        for (value, range, text) in [
+            (&mut config.start_cutoff, 0.0..=10.0, "First part cutoff"),
+            (&mut config.end_cutoff, 0.0..=10.0, "Second part cutoff"),
+            (&mut config.weight, 0.1..=3.0, "Weight exponent"),
+            (&mut config.gender_penalty, 0.0..=5.0, "Gender mismatch penalty"),
+            (&mut config.cluster_allowance, 0.0..=1.0, "Cluster allowance"),
+            (&mut config.falloff_base, 1.0..=4.0, "Insertion falloff"),
+            (&mut config.falloff_spread, 0.01..=4.0, "Insertion falloff spread"),
+        ] {
+            changed |= ui.add(egui::Slider::new(value, range).text(text)).changed();
+        }
+        changed |= ui
+            .add(egui::Slider::new(&mut config.min_join_count, 0..=10).text("Minimum join count"))
+            .on_hover_text("How often the corpus has to join two syllables the same way.")
+            .changed();
+        let gender = &mut config.gender_weights;
+        changed |= ui
+            .add(egui::Slider::new(&mut gender.first, 0.0..=1.0).text("First part gender weight"))
+            .changed();
+        gender.last = gender.last.min(1. - gender.first);
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut gender.last, 0.0..=1. - gender.first)
+                    .text("Last part gender weight"),
+            )
+            .changed();
+        gender.average = (1. - gender.first - gender.last).max(0.);
+        ui.label(format!("Average gender weight: {:.2}", gender.average));
+        if let Some(report) = &self.gender_report {
+            ui.label(format!("Gender classifier: {report}"));
+        }
+        ui.horizontal(|ui| {
+            changed |= ui.button("New sample").clicked();
+            if ui.button("Reset").clicked() {
+                self.load_config();
+                changed = true;
+            }
+            if ui.button("Save config").clicked() {
+                self.save_config();
+            }
+        });
+        if changed || self.preview_stale {
+            self.refresh_preview();
+        }
+        ui.horizontal_wrapped(|ui| {
+            for name in &self.preview {
+                ui.label(name.to_string());
+            }
+        });
+    }
+
+    /// Saves the config where it was loaded from: the pack if it has generator settings,
+    /// `generator.toml` otherwise.
+    fn save_config(&mut self) {
+        let pack_path = self.pack_path.clone().filter(|_| self.pack.generator.is_some());
+        let (path, saved) = match &pack_path {
+            Some(path) => (
+                path.clone(),
+                CulturePack::save_generator(path, &self.config).map_err(|e| e.to_string()),
+            ),
+            None => (
+                CONFIG_FILE.to_owned(),
+                self.config.save(CONFIG_FILE).map_err(|e| e.to_string()),
+            ),
+        };
+        self.corpus_notice = Some(match saved {
+            Ok(()) => {
+                if pack_path.is_some() {
+                    self.pack.generator = Some(self.config.clone());
+                }
+                format!("Saved to {path}.")
+            }
+            Err(e) => e,
+        });
+    }
+
+    fn select_pack(&mut self, path: &str) {
+        match CulturePack::load(path) {
+            Ok(pack) => {
+                self.pack = pack;
+                self.pack_path = Some(path.to_owned());
+                self.corpora.clear();
+                self.name_gen_settings.blend.clear();
+                self.generated.clear();
+                self.load_from_files();
+                self.load_config();
+            }
+            Err(e) => self.corpus_notice = Some(format!("{path}: {e}")),
+        }
+    }
+
+    fn pack_ui(&mut self, ui: &mut egui::Ui) {
+        let mut selected = None;
+        egui::ComboBox::from_label("Culture pack")
+            .selected_text(&self.pack.name)
+            .show_ui(ui, |ui| {
+                for path in available_packs() {
+                    if ui.selectable_label(false, &path).clicked() {
+                        selected = Some(path);
+                    }
+                }
+            });
+        if let Some(path) = selected {
+            self.select_pack(&path);
+        }
+    }
+
+    fn add_corpus(&mut self) {
+        let (label, path) = std::mem::take(&mut self.new_corpus);
+        let label = if label.is_empty() { path.clone() } else { label };
+        match Corpus::load(&label, &path, &self.active_filter) {
+            Ok((corpus, _)) => {
+                self.corpora.retain(|c| c.label != label);
+                self.corpora.push(corpus);
+                let blend = &mut self.name_gen_settings.blend;
+                blend.retain(|w| w.corpus != label);
+                blend.push(BlendWeight {
+                    corpus: label,
+                    weight: 1.,
+                });
+                self.corpus_notice = None;
+                self.blend();
+            }
+            Err(e) => self.corpus_notice = Some(format!("Could not load {path}: {e}")),
+        }
+    }
+
+    /// Mixes the loaded corpora according to the current blend weights.
+    fn blend(&mut self) {
+        let blend = &mut self.name_gen_settings.blend;
+        for c in &self.corpora {
+            if !blend.iter().any(|w| w.corpus == c.label) {
+                let weight = if blend.is_empty() { 1. } else { 0. };
+                blend.push(BlendWeight {
+                    corpus: c.label.clone(),
+                    weight,
+                });
+            }
+        }
+        (self.syllables, self.parts, self.names) = blend_corpora(&self.corpora, blend);
+        self.active_blend.clone_from(blend);
+        self.models = CorpusModels::learn(&self.names, &self.pack);
+        self.gender_report = Some(GenderClassifier::cross_validate(
+            &self.names,
+            &self.pack.phonemes,
+            DEFAULT_FOLDS,
+        ));
+    }
+
+    fn corpora_ui(&mut self, ui: &mut egui::Ui) {
+        let mut removed = None;
+        for (i, corpus) in self.corpora.iter().enumerate() {
+            ui.horizontal(|ui| {
+                let blend = &mut self.name_gen_settings.blend;
+                if let Some(w) = blend.iter_mut().find(|w| w.corpus == corpus.label) {
+                    ui.add(egui::Slider::new(&mut w.weight, 0.0..=1.0).text(&corpus.label));
+                }
+                ui.label(format!("{} names", corpus.names.len()));
+                if i > 0 && ui.small_button("Remove").clicked() {
+                    removed = Some(i);
+                }
+            });
+        }
+        if let Some(i) = removed {
+            self.corpora.remove(i);
+            self.blend();
+        }
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.new_corpus.0).hint_text("label"));
+            ui.add(egui::TextEdit::singleline(&mut self.new_corpus.1).hint_text("corpus.csv"));
+            if ui.button("Add corpus").clicked() && !self.new_corpus.1.is_empty() {
+                self.add_corpus();
+            }
+        });
+    }
+
+    fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        // The central panel the region left after adding TopPanel's and SidePanel's
+        ui.heading(&self.pack.labels.title);
+        self.pack_ui(ui);
+
+        ui.label(format!("Names: {}", self.names.len()));
+        ui.label(format!("Syllables: {}", self.syllables.len()));
+        ui.label(format!("Name Parts: {}", self.parts.len()));
+        ui.horizontal(|ui| {
+            if ui.button("Reload Data").clicked() {
+                self.load_from_files();
+            }
+            if ui.button("Reload Config").clicked() {
+                self.corpus_notice = None;
+                self.load_config();
+            }
+        });
+
+        ui.separator();
+        ui.add(
+            egui::Slider::new(&mut self.name_gen_settings.length, 1.0..=4.0)
+                .text("Length")
+                .step_by(0.1),
+        );
+        ui.add(
+            egui::Slider::new(&mut self.name_gen_settings.amount, 1..=50)
+                .logarithmic(true)
+                .text("Amount"),
+        );
+
+        ui.horizontal(|ui| {
+            ui.label("female");
+            ui.add(
+                egui::Slider::new(&mut self.name_gen_settings.gender_ratio, 0.0..=1.0)
+                    .show_value(false),
+            );
+            ui.label("male");
+            ui.separator();
+            let val = gender_text(self.name_gen_settings.gender_ratio);
+            ui.label(val);
+        });
+        self.sampling_ui(ui);
+        ui.checkbox(
+            &mut self.name_gen_settings.omit_reserved,
+            format!(
+                "Omit reserved ({})",
+                self.pack
+                    .reserved
+                    .iter()
+                    .map(|r| r.segment.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        );
+
+        self.sections_ui(ui);
+        if let Some(notice) = &self.corpus_notice {
+            ui.label(notice);
+        }
+        if let Some(notice) = &self.generation_notice {
+            ui.label(notice);
+        }
+
+        if ui.button("Generate Names").clicked() {
+            self.update_corpus();
+            self.generated = self
+                .generate(&self.name_gen_settings.clone())
+                .into_iter()
+                .map(|v| (v, false))
+                .collect();
+        }
+    }
+
+    /// How names are drawn and written, beyond their length and gender.
+    fn sampling_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let tolerance = &mut self.name_gen_settings.gender_tolerance;
+            let mut strict = tolerance.is_some();
+            if ui
+                .checkbox(&mut strict, "Strict gender")
+                .on_hover_text("Only keep names the classifier rates close to the slider.")
+                .changed()
+            {
+                *tolerance = strict.then_some(DEFAULT_GENDER_TOLERANCE);
+            }
+            if let Some(tolerance) = tolerance {
+                ui.add(egui::Slider::new(tolerance, 0.05..=0.5).text("Tolerance"));
+            }
+        });
+        let mut creativity = self.name_gen_settings.sampling.creativity();
+        if ui
+            .add(egui::Slider::new(&mut creativity, 0.0..=1.0).text("Creativity"))
+            .on_hover_text("Low values stick to common segments, high values favour rare ones.")
+            .changed()
+        {
+            self.name_gen_settings.sampling = Sampling::from_creativity(creativity);
+        }
+        ui.add(
+            egui::Slider::new(&mut self.name_gen_settings.apostrophes, 0.0..=3.0)
+                .text("Apostrophe frequency"),
+        )
+        .on_hover_text("1 uses apostrophes as often as the corpus, 0 never.");
+        let style = &mut self.name_gen_settings.style;
+        egui::ComboBox::from_label("Joiner style")
+            .selected_text(style.map_or("as in corpus", JoinerStyle::text))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(style, None, "as in corpus");
+                for choice in JoinerStyle::ALL {
+                    ui.selectable_value(style, Some(choice), choice.text());
+                }
+            });
+        ui.checkbox(&mut self.name_gen_settings.spellings, "Corpus spellings")
+            .on_hover_text("Write syllables like the corpus does, e.g. ka as ca or kha.");
+    }
+
+    fn sections_ui(&mut self, ui: &mut egui::Ui) {
+        if !self.labels.is_empty() {
+            ui.collapsing("Corpus filter", |ui| {
+                let filter = &mut self.name_gen_settings.filter;
+                label_toggles(ui, "Only:", &self.labels, &mut filter.include);
+                label_toggles(ui, "Exclude:", &self.labels, &mut filter.exclude);
+            });
+        }
+        ui.collapsing("Constraints", |ui| self.constraints_ui(ui));
+        ui.collapsing("Diversity", |ui| self.diversity_ui(ui));
+        ui.collapsing("Family", |ui| self.family_ui(ui));
+        ui.collapsing("Variants", |ui| self.variants_ui(ui));
+        ui.collapsing("Blend names", |ui| self.blend_ui(ui));
+        ui.collapsing("Reserved segments", |ui| self.reserved_ui(ui));
+        ui.collapsing("Deny-list", |ui| self.deny_list_ui(ui));
+        ui.collapsing("Corpora", |ui| self.corpora_ui(ui));
+        ui.collapsing("Presets", |ui| self.presets_ui(ui));
+        ui.collapsing("Advanced", |ui| self.advanced_ui(ui));
+    }
+
+    fn presets_ui(&mut self, ui: &mut egui::Ui) {
+        let mut removed = None;
+        for (i, (name, options)) in self.presets.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button(name).clicked() {
+                    self.name_gen_settings = options.clone();
+                }
+                if ui.small_button("Delete").clicked() {
+                    removed = Some(i);
+                }
+            });
+        }
+        if let Some(i) = removed {
+            self.presets.remove(i);
+        }
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.preset_name).hint_text("preset name"));
+            if ui.button("Save preset").clicked() && !self.preset_name.is_empty() {
+                let name = std::mem::take(&mut self.preset_name);
+                self.presets.retain(|(n, _)| *n != name);
+                self.presets.push((name, self.name_gen_settings.clone()));
+            }
+        });
     }
 }
 
@@ -107,63 +992,25 @@ impl eframe::App for NameApp {
         //     ui.separator();
         // }
 
-        egui::TopBottomPanel::top("Settings").show(ctx, |ui| {
-            // The central panel the region left after adding TopPanel's and SidePanel's
-            ui.heading("Troll Name Generator");
-
-            ui.label(format!("Names: {}", self.names.len()));
-            ui.label(format!("Syllables: {}", self.syllables.len()));
-            ui.label(format!("Name Parts: {}", self.parts.len()));
-            if ui.button("Reload Data").clicked() {
-                self.load_from_files();
-            }
-
-            ui.separator();
-            ui.add(
-                egui::Slider::new(&mut self.name_gen_settings.length, 1.0..=4.0)
-                    .text("Length")
-                    .step_by(0.1),
-            );
-            ui.add(
-                egui::Slider::new(&mut self.name_gen_settings.amount, 1..=50)
-                    .logarithmic(true)
-                    .text("Amount"),
-            );
-
-            ui.horizontal(|ui| {
-                ui.label("female");
-                ui.add(
-                    egui::Slider::new(&mut self.name_gen_settings.gender_ratio, 0.0..=1.0)
-                        .show_value(false),
-                );
-                ui.label("male");
-                ui.separator();
-                let val = gender_text(self.name_gen_settings.gender_ratio);
-                ui.label(val);
-            });
-            ui.checkbox(
-                &mut self.name_gen_settings.omit_reserved,
-                "Omit reserved (jin, fon, zul, zen)",
-            );
-
-            if ui.button("Generate Names").clicked() {
-                self.generated = generate_names_from_parts(
-                    &self.parts,
-                    &self.syllables,
-                    &self.names,
-                    &self.name_gen_settings,
-                )
-                .into_iter()
-                .map(|v| (v, false))
-                .collect();
-            }
-        });
+        egui::TopBottomPanel::top("Settings").show(ctx, |ui| self.settings_ui(ui));
         let mut selected = None;
+        let mut vary = None;
+        #[cfg(not(target_arch = "wasm32"))]
+        let mut speak = None;
 
         if let Some((i, (n, _))) = self.generated.iter().enumerate().find(|(_, (_, e))| *e) {
             egui::SidePanel::right("test").show(ctx, |ui| {
                 selected = Some(i);
-                ui.heading(n.to_string());
+                ui.horizontal(|ui| {
+                    ui.heading(n.to_string());
+                    if ui.button("Variants").clicked() {
+                        vary = Some(n.to_string());
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui.button("Save WAV").clicked() {
+                        speak = Some(n.clone());
+                    }
+                });
                 ui.horizontal(|ui| {
                     ui.label("Gender: ");
                     let (v, text) = if n.gender() > 0.5 {
@@ -173,6 +1020,10 @@ impl eframe::App for NameApp {
                     };
                     ui.label(format!("{:.0}% {}", v * 100., text));
                 });
+                ui.horizontal(|ui| {
+                    ui.label("Classifier: ");
+                    ui.label(self.models.gender.predict(&n.to_string()).to_string());
+                });
 
                 ui.horizontal(|ui| {
                     for (i, v) in n.elements.iter().enumerate() {
@@ -182,35 +1033,33 @@ impl eframe::App for NameApp {
                         } else {
                             false
                         };
-                        if ui.selectable_label(curr, v.to_string()).clicked() {
+                        if ui
+                            .selectable_label(curr, v.to_string())
+                            .on_hover_text(format!("from {}", v.corpus))
+                            .clicked()
+                        {
                             if curr { self.selected_label = None; } else {
                                 self.selected_label = Some(i);
                             }
                         };
                     }
                 });
-                if let Some(sl) = self.selected_label {
+                if let Some(segment) = self.selected_label.and_then(|sl| n.elements.get(sl)) {
                     ui.separator();
-                    ui.label("Derived from:");
-                    egui::ScrollArea::vertical().show(ui, |ui| {
-                        for name in n.elements[sl].derived_names.iter() {
-                            ui.horizontal(|ui| {
-                                ui.label(format!("{} - (", name));
-                                ui.hyperlink_to(
-                                    "link",
-                                    format!(
-                                        "https://wowpedia.fandom.com/wiki/{}",
-                                        urlencoding::encode(name)
-                                    ),
-                                );
-                                ui.label(")");
-                            });
-                        }
-                    });
+                    derived_names_ui(ui, segment, self.pack.labels.name_link.as_deref());
                 }
             });
         }
 
+        if let Some(seed) = vary {
+            self.variant_seed = seed;
+            self.generate_variants();
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(name) = speak {
+            self.save_wav(&name);
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             if self.generated.len() > 0 {
                 let mut changed = None;
@@ -227,6 +1076,8 @@ impl eframe::App for NameApp {
                                 changed = Some(i);
                             }
                         });
+                        let pronunciation = self.models.pronouncer.pronounce(n);
+                        ui.weak(format!("{}  {}", pronunciation.respelling, pronunciation.ipa));
                     }
                 });
                 if let (Some(prev), Some(selected)) = (selected, changed) {
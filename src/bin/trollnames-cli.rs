@@ -0,0 +1,433 @@
+#![warn(clippy::all, rust_2018_idioms)]
+
+use std::io::Write as _;
+use std::process::ExitCode;
+use trollnames::config::GeneratorConfig;
+use trollnames::constraints::{Bounds, Constraints};
+use trollnames::culture::CulturePack;
+use trollnames::data_processing::{Corpus, CorpusFilter, append_records, read_records};
+use trollnames::deny_list::DenyList;
+use trollnames::family::{FamilyOptions, generate_family};
+use trollnames::gender::{DEFAULT_FOLDS, GenderClassifier};
+use trollnames::import::{
+    ColumnMapping, ImportCandidate, existing_names, known_titles, table_candidates,
+    wowhead_candidates,
+};
+use trollnames::name_gen::{
+    CorpusModels, Diversity, NameGenOptions, Sampling, generate_names_from_parts,
+};
+use trollnames::speech::{wav_file_name, write_wav};
+use trollnames::variants::{blend_names, generate_variants};
+
+const USAGE: &str = "usage: trollnames-cli <command> [options]
+
+commands:
+  generate [--pack <toml>] [--config <toml>] [--amount <n>] [--length <n>]
+           [--gender <0-1>] [--creativity <0-1>] [--temperature <t>]
+           [--top-k <k>] [--top-p <0-1>]
+           [--initial <letters>] [--syllables <n|min-max>] [--letters <n|min-max>]
+           [--require <a,b>] [--forbid <a,b>] [--include <regex>]
+           [--exclude <regex>] [--deny-list <txt>] [--default-deny-list <bool>]
+           [--unique <bool>] [--max-part-reuse <n>] [--min-distance <n>]
+           [--apostrophes <scale>]
+           [--style <plain|capitalized|hyphenated|closed>] [--spellings <bool>]
+           [--gender-tolerance <0-1>]
+           [--pronounce <bool>] [--json <bool>] [--wav <dir>]
+      Print generated names. Without --config the pack's generator settings
+      or generator.toml are used. --temperature, --top-k and --top-p override
+      the values derived from --creativity. Ranges may leave out either end,
+      as in -9 for at most 9. --deny-list adds entries in the syntax of
+      data/deny_list.txt to the bundled list. --apostrophes scales how often
+      joiners appear compared to the corpus, 0 leaves them out. Without
+      --style joiners are written like in as many corpus names. With
+      --spellings false syllables are written as their keys, not the way
+      the corpus spells them. With --gender-tolerance only names the gender
+      classifier rates that close to --gender are kept. --pronounce adds a
+      respelling with the stressed syllable in capitals and an IPA
+      transcription, --json prints both with every name as a JSON array.
+      --wav speaks every name with a robotic built-in voice into a WAV file
+      in the directory.
+  family [--kind <siblings|clan|lineage>] [--size <n>] [--json <bool>]
+         [generate options]
+      Print a group of related names with the shared parts in brackets, or
+      the whole group with pronunciations as one JSON object.
+  variants <name> [generate options]
+      Print --amount variants of a name, each with the change that made it.
+  blend <name> <name>... [generate options]
+      Print the --amount best blends of the names, each with the segments
+      every name contributed.
+  gender [<name>...] [--folds <n>] [generate options]
+      Print the gender lean of any names with how confident the classifier
+      trained on the corpus is, and its cross-validated accuracy.
+  import-wowhead <page>... [--corpus <csv>] [--out <csv>]
+      Parse saved Wowhead search pages and print candidate corpus rows.
+  import-csv <table> --name <column> --race <column> [--gender <column>]
+             [--only <race>] [--corpus <csv>] [--out <csv>]
+      Append the names of a locally exported creature table to the corpus.
+      Rows are kept if their race column contains --only (default: troll).
+      With --out the candidates are written there for review instead.";
+
+type CliResult = Result<(), Box<dyn std::error::Error>>;
+
+/// Splits the arguments of a command into positional values and `--key value` options.
+fn parse_args(args: &[String]) -> (Vec<&str>, Vec<(&str, &str)>) {
+    let mut positional = vec![];
+    let mut options = vec![];
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if let Some(key) = arg.strip_prefix("--") {
+            options.push((key, iter.next().map_or("", String::as_str)));
+        } else {
+            positional.push(arg.as_str());
+        }
+    }
+    (positional, options)
+}
+
+fn option<'a>(options: &[(&str, &'a str)], key: &str) -> Option<&'a str> {
+    options.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+fn write_candidates(candidates: &[ImportCandidate], out: Option<&str>) -> CliResult {
+    let writer: Box<dyn std::io::Write> = match out {
+        Some(path) => Box::new(std::fs::File::create(path)?),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let mut wtr = csv::Writer::from_writer(writer);
+    for c in candidates {
+        wtr.serialize(c)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+fn optional_value<T: std::str::FromStr<Err: std::fmt::Display>>(
+    options: &[(&str, &str)],
+    key: &str,
+) -> Result<Option<T>, String> {
+    option(options, key)
+        .map(|v| {
+            v.parse()
+                .map_err(|e| format!("--{key}: `{v}` invalid value ({e})"))
+        })
+        .transpose()
+}
+
+fn value<T: std::str::FromStr<Err: std::fmt::Display>>(
+    options: &[(&str, &str)],
+    key: &str,
+    default: T,
+) -> Result<T, String> {
+    Ok(optional_value(options, key)?.unwrap_or(default))
+}
+
+/// Everything the generating commands share, read from the common options.
+struct Generator {
+    pack: CulturePack,
+    config: GeneratorConfig,
+    settings: NameGenOptions,
+    corpus: Corpus,
+}
+
+fn generator(options: &[(&str, &str)]) -> Result<Generator, Box<dyn std::error::Error>> {
+    let pack =
+        option(options, "pack").map_or_else(|| Ok(CulturePack::default()), CulturePack::load)?;
+    let config = match (option(options, "config"), &pack.generator) {
+        (Some(path), _) => GeneratorConfig::load(path)?,
+        (None, Some(config)) => config.clone(),
+        (None, None) => GeneratorConfig::load_default()?,
+    };
+    let sampling = Sampling::from_creativity(value(options, "creativity", 0.5)?);
+    let sampling = Sampling {
+        temperature: value(options, "temperature", sampling.temperature)?,
+        top_k: optional_value(options, "top-k")?.or(sampling.top_k),
+        top_p: optional_value(options, "top-p")?.or(sampling.top_p),
+    };
+    if sampling.temperature <= 0. {
+        return Err("--temperature must be greater than 0".into());
+    }
+    let list =
+        |key| option(options, key).map_or(vec![], |v| v.split(',').map(str::to_owned).collect());
+    let constraints = Constraints {
+        initial: option(options, "initial").unwrap_or_default().to_owned(),
+        syllables: value(options, "syllables", Bounds::default())?,
+        letters: value(options, "letters", Bounds::default())?,
+        required: list("require"),
+        forbidden: list("forbid"),
+        include: option(options, "include").map(str::to_owned),
+        exclude: option(options, "exclude").map(str::to_owned),
+    };
+    let deny_list = DenyList {
+        use_default: value(options, "default-deny-list", true)?,
+        custom: option(options, "deny-list")
+            .map(std::fs::read_to_string)
+            .transpose()?
+            .unwrap_or_default(),
+    };
+    let diversity = Diversity {
+        unique: value(options, "unique", true)?,
+        max_part_reuse: optional_value(options, "max-part-reuse")?,
+        min_distance: optional_value(options, "min-distance")?,
+    };
+    let defaults = NameGenOptions::default();
+    let settings = NameGenOptions {
+        amount: value(options, "amount", defaults.amount)?,
+        length: value(options, "length", defaults.length)?,
+        gender_ratio: value(options, "gender", defaults.gender_ratio)?,
+        apostrophes: value(options, "apostrophes", defaults.apostrophes)?,
+        style: optional_value(options, "style")?,
+        spellings: value(options, "spellings", true)?,
+        gender_tolerance: optional_value(options, "gender-tolerance")?,
+        sampling,
+        constraints,
+        deny_list,
+        diversity,
+        ..defaults
+    };
+    settings.validate()?;
+    let (corpus, _) = Corpus::load(&pack.name, &pack.corpus, &CorpusFilter::default())?;
+    Ok(Generator {
+        pack,
+        config,
+        settings,
+        corpus,
+    })
+}
+
+fn generate(args: &[String]) -> CliResult {
+    let (_, options) = parse_args(args);
+    let Generator {
+        pack,
+        config,
+        settings,
+        corpus,
+    } = generator(&options)?;
+    let models = CorpusModels::learn(&corpus.names, &pack);
+    let generation = generate_names_from_parts(
+        &corpus.parts,
+        &corpus.syllables,
+        &models,
+        &settings,
+        &pack,
+        &config,
+    )?;
+    let pronouncer = &models.pronouncer;
+    let mut out = std::io::stdout().lock();
+    if value(&options, "json", false)? {
+        let names = generation
+            .names
+            .iter()
+            .map(|name| {
+                let pronunciation = pronouncer.pronounce(name);
+                serde_json::json!({
+                    "name": name.to_string(),
+                    "respelling": pronunciation.respelling,
+                    "ipa": pronunciation.ipa,
+                })
+            })
+            .collect::<Vec<_>>();
+        writeln!(out, "{}", serde_json::to_string_pretty(&names)?)?;
+    } else if value(&options, "pronounce", false)? {
+        for name in &generation.names {
+            writeln!(out, "{name}  {}", pronouncer.pronounce(name))?;
+        }
+    } else {
+        for name in &generation.names {
+            writeln!(out, "{name}")?;
+        }
+    }
+    if let Some(dir) = option(&options, "wav") {
+        std::fs::create_dir_all(dir)?;
+        for name in &generation.names {
+            let path = std::path::Path::new(dir).join(wav_file_name(&name.to_string()));
+            write_wav(&pronouncer.pronounce(name), &path)?;
+        }
+    }
+    if let Some(report) = generation.report() {
+        writeln!(std::io::stderr(), "{report}")?;
+    }
+    Ok(())
+}
+
+fn family(args: &[String]) -> CliResult {
+    let (_, options) = parse_args(args);
+    let Generator {
+        pack,
+        config,
+        settings,
+        corpus,
+    } = generator(&options)?;
+    let family_options = FamilyOptions {
+        kind: value(&options, "kind", FamilyOptions::default().kind)?,
+        size: value(&options, "size", FamilyOptions::default().size)?,
+    };
+    let family = generate_family(
+        &corpus.parts,
+        &corpus.syllables,
+        &CorpusModels::learn(&corpus.names, &pack),
+        &settings,
+        &pack,
+        &config,
+        &family_options,
+    )?;
+    if value(&options, "json", false)? {
+        writeln!(std::io::stdout(), "{}", family.to_json()?)?;
+    } else {
+        write!(std::io::stdout(), "{family}")?;
+    }
+    if let Some(report) = &family.report {
+        writeln!(std::io::stderr(), "{report}")?;
+    }
+    Ok(())
+}
+
+fn variants(args: &[String]) -> CliResult {
+    let (seed, options) = parse_args(args);
+    let Some(seed) = seed.first() else {
+        return Err(USAGE.into());
+    };
+    let Generator {
+        pack,
+        config,
+        settings,
+        corpus,
+    } = generator(&options)?;
+    let variants = generate_variants(
+        seed,
+        &corpus.parts,
+        &corpus.syllables,
+        &settings,
+        &pack,
+        &config,
+    )?;
+    let mut out = std::io::stdout().lock();
+    writeln!(out, "{}", variants.original)?;
+    for variant in &variants.variants {
+        writeln!(out, "  {} ({})", variant.name, variant.mutation.text())?;
+    }
+    Ok(())
+}
+
+fn blend(args: &[String]) -> CliResult {
+    let (names, options) = parse_args(args);
+    let Generator {
+        config,
+        settings,
+        corpus,
+        ..
+    } = generator(&options)?;
+    let blend = blend_names(&names, &corpus.parts, &corpus.syllables, &settings, &config)?;
+    let mut out = std::io::stdout().lock();
+    for blended in &blend.blends {
+        let contributions = blended
+            .contributions()
+            .into_iter()
+            .filter_map(|(text, parent)| {
+                let parent = blend.parents.get(parent)?;
+                Some(format!("{text} from {parent}"))
+            })
+            .collect::<Vec<_>>();
+        writeln!(out, "{} ({})", blended.name, contributions.join(", "))?;
+    }
+    Ok(())
+}
+
+fn gender(args: &[String]) -> CliResult {
+    let (names, options) = parse_args(args);
+    let Generator { pack, corpus, .. } = generator(&options)?;
+    let classifier = GenderClassifier::train(&corpus.names, &pack.phonemes);
+    let mut out = std::io::stdout().lock();
+    for name in names {
+        writeln!(out, "{name}: {}", classifier.predict(name))?;
+    }
+    let folds = value(&options, "folds", DEFAULT_FOLDS)?;
+    let labelled = GenderClassifier::labelled(&corpus.names);
+    if !(2..=labelled).contains(&folds) {
+        return Err(format!("--folds: expected 2 to {labelled}, got {folds}").into());
+    }
+    let report = GenderClassifier::cross_validate(&corpus.names, &pack.phonemes, folds);
+    writeln!(std::io::stderr(), "{report}")?;
+    Ok(())
+}
+
+fn import_wowhead(args: &[String]) -> CliResult {
+    let (pages, options) = parse_args(args);
+    if pages.is_empty() {
+        return Err(USAGE.into());
+    }
+    let corpus = read_records(option(&options, "corpus").unwrap_or("data/syllables.csv"))?;
+    let titles = known_titles(&corpus);
+    let mut existing = existing_names(&corpus);
+    let mut candidates = vec![];
+    for page in pages {
+        let content = std::fs::read_to_string(page)?;
+        let found = wowhead_candidates(&content, &titles, &mut existing)
+            .map_err(|e| format!("{page}: {e}"))?;
+        writeln!(std::io::stderr(), "{page}: {} new candidates", found.len())?;
+        candidates.extend(found);
+    }
+    write_candidates(&candidates, option(&options, "out"))
+}
+
+fn import_csv(args: &[String]) -> CliResult {
+    let (tables, options) = parse_args(args);
+    let (Some(table), Some(name)) = (tables.first(), option(&options, "name")) else {
+        return Err(USAGE.into());
+    };
+    let Some(race) = option(&options, "race") else {
+        return Err(
+            "--race is required, without a race column every creature would be imported".into(),
+        );
+    };
+    let mapping = ColumnMapping {
+        name: name.to_owned(),
+        race: race.to_owned(),
+        gender: option(&options, "gender").map(str::to_owned),
+    };
+    let race_filter = option(&options, "only").unwrap_or("troll");
+    let corpus_path = option(&options, "corpus").unwrap_or("data/syllables.csv");
+    let corpus = read_records(corpus_path)?;
+    let titles = known_titles(&corpus);
+    let mut existing = existing_names(&corpus);
+    let candidates = table_candidates(
+        std::fs::File::open(table)?,
+        &mapping,
+        race_filter,
+        &titles,
+        &mut existing,
+    )
+    .map_err(|e| format!("{table}: {e}"))?;
+    writeln!(
+        std::io::stderr(),
+        "{table}: {} new candidates",
+        candidates.len()
+    )?;
+    if let Some(out) = option(&options, "out") {
+        return write_candidates(&candidates, Some(out));
+    }
+    let records = candidates
+        .into_iter()
+        .map(ImportCandidate::into_record)
+        .collect::<Vec<_>>();
+    append_records(corpus_path, &records)?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let result = match args.split_first() {
+        Some((command, rest)) if command == "generate" => generate(rest),
+        Some((command, rest)) if command == "family" => family(rest),
+        Some((command, rest)) if command == "variants" => variants(rest),
+        Some((command, rest)) if command == "blend" => blend(rest),
+        Some((command, rest)) if command == "gender" => gender(rest),
+        Some((command, rest)) if command == "import-wowhead" => import_wowhead(rest),
+        Some((command, rest)) if command == "import-csv" => import_csv(rest),
+        _ => Err(USAGE.into()),
+    };
+    if let Err(e) = result {
+        writeln!(std::io::stderr(), "{e}").ok();
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Formatter;
+
+/// Config read from the working directory when no other file is given.
+pub const CONFIG_FILE: &str = "generator.toml";
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+    Write(toml::ser::Error),
+    Invalid {
+        key: &'static str,
+        value: f32,
+        expected: &'static str,
+    },
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "could not read generator config: {e}"),
+            Self::Toml(e) => write!(f, "invalid generator config: {e}"),
+            Self::Write(e) => write!(f, "could not write generator config: {e}"),
+            Self::Invalid {
+                key,
+                value,
+                expected,
+            } => write!(
+                f,
+                "invalid generator setting: `{key}` is {value}, expected {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(e: toml::de::Error) -> Self {
+        Self::Toml(e)
+    }
+}
+
+impl From<toml::ser::Error> for ConfigError {
+    fn from(e: toml::ser::Error) -> Self {
+        Self::Write(e)
+    }
+}
+
+/// How much the segments of a generated name count towards its gender lean.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenderWeights {
+    /// Weight of the average over all segments.
+    pub average: f32,
+    /// Weight of the first segment.
+    pub first: f32,
+    /// Weight of the last segment.
+    pub last: f32,
+}
+
+impl Default for GenderWeights {
+    fn default() -> Self {
+        Self {
+            average: 0.1,
+            first: 0.45,
+            last: 0.45,
+        }
+    }
+}
+
+/// Tuning knobs of the generator, see `generator.toml` for a documented example.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GeneratorConfig {
+    /// Minimum start value for a part to be used as first part.
+    pub start_cutoff: f32,
+    /// Minimum end value for a part to be used as second part.
+    pub end_cutoff: f32,
+    /// Exponent applied to the positional counts before sampling.
+    pub weight: f32,
+    /// Exponent of the gender match `1 - |gender ratio - segment ratio|`. 0 ignores the
+    /// gender of segments, higher values punish mismatches harder.
+    pub gender_penalty: f32,
+    /// Chance that a join the corpus does not use is kept instead of repaired.
+    pub cluster_allowance: f32,
+    /// How often the corpus has to form a consonant cluster or vowel sequence between two
+    /// syllables for generated names to use it, see [`crate::phonotactics::Phonotactics`].
+    pub min_join_count: usize,
+    /// After every inserted syllable the insertion chance is divided by
+    /// `falloff_base + random * falloff_spread`, with `random` in `[0, 1)`.
+    pub falloff_base: f32,
+    pub falloff_spread: f32,
+    pub gender_weights: GenderWeights,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            start_cutoff: 2.,
+            end_cutoff: 2.,
+            weight: 1.,
+            gender_penalty: 1.,
+            cluster_allowance: 0.05,
+            min_join_count: 2,
+            falloff_base: 1.,
+            falloff_spread: 1.,
+            gender_weights: GenderWeights::default(),
+        }
+    }
+}
+
+pub(crate) fn check(
+    key: &'static str,
+    value: f32,
+    valid: bool,
+    expected: &'static str,
+) -> Result<(), ConfigError> {
+    if valid && value.is_finite() {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid {
+            key,
+            value,
+            expected,
+        })
+    }
+}
+
+impl GeneratorConfig {
+    /// Reads and validates a config file.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read, has unknown keys or holds invalid values.
+    pub fn load(path: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(&std::fs::read_to_string(path)?)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// The config in `generator.toml` if there is one, the defaults otherwise.
+    ///
+    /// # Errors
+    /// Fails if the file exists but is not a valid config.
+    pub fn load_default() -> Result<Self, ConfigError> {
+        match Self::load(CONFIG_FILE) {
+            Err(ConfigError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(Self::default())
+            }
+            result => result,
+        }
+    }
+
+    /// Writes the config as toml, replacing the file and its comments.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be written.
+    pub fn save(&self, path: &str) -> Result<(), ConfigError> {
+        let content = format!(
+            "# Saved from the advanced settings, delete this file to restore the defaults.\n\n{}",
+            toml::to_string(self)?
+        );
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// # Errors
+    /// Names the first value that is out of range.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let g = &self.gender_weights;
+        check(
+            "start_cutoff",
+            self.start_cutoff,
+            self.start_cutoff >= 0.,
+            "at least 0",
+        )?;
+        check(
+            "end_cutoff",
+            self.end_cutoff,
+            self.end_cutoff >= 0.,
+            "at least 0",
+        )?;
+        check("weight", self.weight, self.weight > 0., "greater than 0")?;
+        check(
+            "gender_penalty",
+            self.gender_penalty,
+            self.gender_penalty >= 0.,
+            "at least 0",
+        )?;
+        check(
+            "cluster_allowance",
+            self.cluster_allowance,
+            (0.0..=1.0).contains(&self.cluster_allowance),
+            "between 0 and 1",
+        )?;
+        check(
+            "falloff_base",
+            self.falloff_base,
+            self.falloff_base >= 1.,
+            "at least 1",
+        )?;
+        check(
+            "falloff_spread",
+            self.falloff_spread,
+            self.falloff_spread >= 0. && self.falloff_base + self.falloff_spread > 1.,
+            "at least 0 and above 0 if falloff_base is 1, or names never stop growing",
+        )?;
+        for (key, value) in [
+            ("gender_weights.average", g.average),
+            ("gender_weights.first", g.first),
+            ("gender_weights.last", g.last),
+        ] {
+            check(key, value, value >= 0., "at least 0")?;
+        }
+        let sum = g.average + g.first + g.last;
+        check(
+            "gender_weights",
+            sum,
+            (sum - 1.).abs() < 1e-3,
+            "average + first + last to add up to 1",
+        )
+    }
+}
@@ -0,0 +1,181 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt::Formatter;
+
+/// How many candidates may be drawn per requested name before giving up.
+pub const ATTEMPTS_PER_NAME: usize = 200;
+
+#[derive(Debug)]
+pub enum ConstraintError {
+    Regex(String, regex::Error),
+    /// The constraints contradict each other or the corpus.
+    Unsatisfiable(String),
+}
+
+impl std::fmt::Display for ConstraintError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Regex(pattern, e) => write!(f, "invalid pattern `{pattern}`: {e}"),
+            Self::Unsatisfiable(reason) => write!(f, "constraints cannot be satisfied: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ConstraintError {}
+
+/// Inclusive bounds, either side may be left open.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bounds {
+    pub min: Option<usize>,
+    pub max: Option<usize>,
+}
+
+impl Bounds {
+    pub fn is_empty(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    pub fn contains(&self, value: usize) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+
+    fn check(&self, what: &str) -> Result<(), ConstraintError> {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if min > max => Err(ConstraintError::Unsatisfiable(format!(
+                "at least {min} but at most {max} {what}"
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Parses `3` as exactly 3 and `2-4`, `-9` or `4-` as ranges.
+impl std::str::FromStr for Bounds {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bound = |b: &str| (!b.trim().is_empty()).then(|| b.trim().parse()).transpose();
+        Ok(match s.split_once('-') {
+            Some((min, max)) => Self {
+                min: bound(min)?,
+                max: bound(max)?,
+            },
+            None => Self {
+                min: bound(s)?,
+                max: bound(s)?,
+            },
+        })
+    }
+}
+
+/// Requirements every generated name has to meet. Empty fields do not constrain anything.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Constraints {
+    /// Letters the name has to start with, ignoring case.
+    pub initial: String,
+    pub syllables: Bounds,
+    /// Number of letters, joiners like `'` are not counted.
+    pub letters: Bounds,
+    /// Texts that have to appear in the name, ignoring case.
+    pub required: Vec<String>,
+    /// Texts that must not appear in the name, ignoring case.
+    pub forbidden: Vec<String>,
+    /// Regular expression the displayed name has to match.
+    pub include: Option<String>,
+    /// Regular expression the displayed name must not match.
+    pub exclude: Option<String>,
+}
+
+/// [`Constraints`] with lowercased texts and compiled patterns.
+pub(crate) struct Checker {
+    initial: String,
+    syllables: Bounds,
+    letters: Bounds,
+    required: Vec<String>,
+    forbidden: Vec<String>,
+    include: Option<Regex>,
+    exclude: Option<Regex>,
+}
+
+fn compile(pattern: Option<&String>) -> Result<Option<Regex>, ConstraintError> {
+    pattern
+        .filter(|p| !p.is_empty())
+        .map(|p| Regex::new(p).map_err(|e| ConstraintError::Regex(p.clone(), e)))
+        .transpose()
+}
+
+fn lowercase(texts: &[String]) -> Vec<String> {
+    texts
+        .iter()
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+impl Constraints {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// # Errors
+    /// Fails on invalid patterns and on bounds or texts that contradict each other.
+    pub(crate) fn checker(&self) -> Result<Checker, ConstraintError> {
+        self.syllables.check("syllables")?;
+        self.letters.check("letters")?;
+        let checker = Checker {
+            initial: self.initial.trim().to_lowercase(),
+            syllables: self.syllables,
+            letters: self.letters,
+            required: lowercase(&self.required),
+            forbidden: lowercase(&self.forbidden),
+            include: compile(self.include.as_ref())?,
+            exclude: compile(self.exclude.as_ref())?,
+        };
+        let clash = checker.forbidden.iter().find(|f| {
+            checker.initial.contains(f.as_str())
+                || checker.required.iter().any(|r| r.contains(f.as_str()))
+        });
+        if let Some(f) = clash {
+            return Err(ConstraintError::Unsatisfiable(format!(
+                "`{f}` is both required and forbidden"
+            )));
+        }
+        Ok(checker)
+    }
+}
+
+fn letter_count(text: &str) -> usize {
+    text.chars().filter(|c| c.is_alphabetic()).count()
+}
+
+impl Checker {
+    /// Whether a segment may start a name.
+    pub(crate) fn allows_first(&self, segment: &str) -> bool {
+        let segment = segment.to_lowercase();
+        segment.starts_with(&self.initial) || self.initial.starts_with(&segment)
+    }
+
+    /// Whether a segment may appear anywhere in a name. This only rules out segments
+    /// that break a constraint on their own, joins are checked on the whole name.
+    pub(crate) fn allows_segment(&self, segment: &str) -> bool {
+        let segment = segment.to_lowercase();
+        !self.forbidden.iter().any(|f| segment.contains(f.as_str()))
+            && self
+                .letters
+                .max
+                .is_none_or(|max| letter_count(&segment) <= max)
+    }
+
+    pub(crate) fn allows(&self, name: &str, syllables: usize) -> bool {
+        let lower = name.to_lowercase();
+        lower.starts_with(&self.initial)
+            && self.syllables.contains(syllables)
+            && self.letters.contains(letter_count(name))
+            && self.required.iter().all(|r| lower.contains(r.as_str()))
+            && !self.forbidden.iter().any(|f| lower.contains(f.as_str()))
+            && self.include.as_ref().is_none_or(|r| r.is_match(name))
+            && !self.exclude.as_ref().is_some_and(|r| r.is_match(name))
+    }
+}
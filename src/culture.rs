@@ -0,0 +1,251 @@
+use crate::config::{ConfigError, GeneratorConfig};
+use crate::data_processing::{DEFAULT_CORPUS, Name};
+use crate::joiners::JoinerModel;
+use crate::phonemes::PhonemeRules;
+use serde::{Deserialize, Serialize};
+use std::fmt::Formatter;
+
+/// Where the app looks for `*.toml` culture packs.
+pub const PACK_DIR: &str = "packs";
+
+#[derive(Debug)]
+pub enum PackError {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+    Write(toml::ser::Error),
+    Config(ConfigError),
+}
+
+impl std::fmt::Display for PackError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "could not read culture pack: {e}"),
+            Self::Toml(e) => write!(f, "invalid culture pack: {e}"),
+            Self::Write(e) => write!(f, "could not write culture pack: {e}"),
+            Self::Config(e) => write!(f, "culture pack has an {e}"),
+        }
+    }
+}
+
+impl std::error::Error for PackError {}
+
+impl From<std::io::Error> for PackError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for PackError {
+    fn from(e: toml::de::Error) -> Self {
+        Self::Toml(e)
+    }
+}
+
+impl From<toml::ser::Error> for PackError {
+    fn from(e: toml::ser::Error) -> Self {
+        Self::Write(e)
+    }
+}
+
+/// Texts the GUI shows for a pack.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PackLabels {
+    pub title: String,
+    /// Link shown next to every source name, `{}` is replaced by the url-encoded name.
+    pub name_link: Option<String>,
+}
+
+impl Default for PackLabels {
+    fn default() -> Self {
+        Self {
+            title: "Troll Name Generator".to_owned(),
+            name_link: Some("https://wowpedia.fandom.com/wiki/{}".to_owned()),
+        }
+    }
+}
+
+/// What happens to a reserved segment when "omit reserved" is checked.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReservedPolicy {
+    /// Never used.
+    #[default]
+    Forbid,
+    /// Not used at the start of a name.
+    ForbidStart,
+    /// Not used at the end of a name.
+    ForbidEnd,
+    /// Used, but its weight is multiplied by the factor.
+    DownWeight(f32),
+}
+
+/// Packs may list reserved segments as plain strings, which forbids them entirely.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ReservedEntry {
+    Plain(String),
+    Full {
+        segment: String,
+        #[serde(default)]
+        policy: ReservedPolicy,
+    },
+}
+
+impl From<ReservedEntry> for ReservedSegment {
+    fn from(entry: ReservedEntry) -> Self {
+        match entry {
+            ReservedEntry::Plain(segment) => Self {
+                segment,
+                policy: ReservedPolicy::Forbid,
+            },
+            ReservedEntry::Full { segment, policy } => Self { segment, policy },
+        }
+    }
+}
+
+/// A part or syllable that is kept out of generated names, like the names of loa.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "ReservedEntry")]
+pub struct ReservedSegment {
+    pub segment: String,
+    pub policy: ReservedPolicy,
+}
+
+impl ReservedSegment {
+    /// Factor for the weight of the segment, 0 where it is forbidden.
+    fn factor(&self, start: bool, end: bool) -> f32 {
+        match self.policy {
+            ReservedPolicy::Forbid => 0.,
+            ReservedPolicy::ForbidStart if start => 0.,
+            ReservedPolicy::ForbidEnd if end => 0.,
+            ReservedPolicy::ForbidStart | ReservedPolicy::ForbidEnd => 1.,
+            ReservedPolicy::DownWeight(factor) => factor.max(0.),
+        }
+    }
+}
+
+/// Everything the generator needs to know about one naming culture: the corpus it learns
+/// from and the rules that are not in the corpus itself. Missing keys keep the troll defaults.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CulturePack {
+    pub name: String,
+    /// Path of the corpus csv, relative to the working directory.
+    pub corpus: String,
+    /// Parts and syllables that are left out or down-weighted when "omit reserved" is checked.
+    pub reserved: Vec<ReservedSegment>,
+    /// Which letters are vowels and which letter groups are one sound.
+    pub phonemes: PhonemeRules,
+    /// Text put between the first and second part.
+    pub joiner: String,
+    /// How often the joiner is put between the parts. Without it, how often and where the
+    /// corpus uses the joiner is learned, see [`crate::joiners::JoinerModel`].
+    pub joiner_chance: Option<f32>,
+    pub labels: PackLabels,
+    /// Generator tuning for this culture, replacing the loaded config when the pack is selected.
+    pub generator: Option<GeneratorConfig>,
+}
+
+impl Default for CulturePack {
+    fn default() -> Self {
+        Self {
+            name: "troll".to_owned(),
+            corpus: DEFAULT_CORPUS.to_owned(),
+            reserved: ["jin", "fon", "zen", "zul"]
+                .map(|segment| ReservedSegment {
+                    segment: segment.to_owned(),
+                    policy: ReservedPolicy::Forbid,
+                })
+                .to_vec(),
+            phonemes: PhonemeRules::default(),
+            joiner: "'".to_owned(),
+            joiner_chance: None,
+            labels: PackLabels::default(),
+            generator: None,
+        }
+    }
+}
+
+impl CulturePack {
+    /// The joiner chances and styles learned from the corpus, with the fixed joiner chance
+    /// of the pack if it has one.
+    pub fn joiner_model(&self, names: &[Name]) -> JoinerModel {
+        let learned = JoinerModel::learn(names, &self.joiner, &self.phonemes);
+        match self.joiner_chance {
+            Some(part) => JoinerModel {
+                part,
+                syllable: 0.,
+                ..learned
+            },
+            None => learned,
+        }
+    }
+
+    /// Reads a pack from a toml file.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or is not a valid pack.
+    pub fn load(path: &str) -> Result<Self, PackError> {
+        let pack: Self = toml::from_str(&std::fs::read_to_string(path)?)?;
+        if let Some(generator) = &pack.generator {
+            generator.validate().map_err(PackError::Config)?;
+        }
+        Ok(pack)
+    }
+
+    /// Replaces the `[generator]` table of the pack file at `path` with `config`, keeping
+    /// the rest of the file and its comments.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or written.
+    pub fn save_generator(path: &str, config: &GeneratorConfig) -> Result<(), PackError> {
+        #[derive(Serialize)]
+        struct Table<'a> {
+            generator: &'a GeneratorConfig,
+        }
+        let content = std::fs::read_to_string(path)?;
+        let mut in_generator = false;
+        let mut kept = vec![];
+        for line in content.lines() {
+            let header = line.trim();
+            if header.starts_with('[') {
+                let header = header.trim_matches(['[', ']']).trim();
+                in_generator = header == "generator" || header.starts_with("generator.");
+            }
+            if !in_generator {
+                kept.push(line);
+            }
+        }
+        let content = format!(
+            "{}\n\n{}",
+            kept.join("\n").trim_end(),
+            toml::to_string(&Table { generator: config })?
+        );
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Weight factor of a segment at the start, end or, with neither, in the middle of a name.
+    pub fn reserved_factor(&self, segment: &str, start: bool, end: bool) -> f32 {
+        self.reserved
+            .iter()
+            .filter(|r| r.segment.eq_ignore_ascii_case(segment))
+            .map(|r| r.factor(start, end))
+            .product()
+    }
+}
+
+/// Paths of all packs in [`PACK_DIR`], sorted by name.
+pub fn available_packs() -> Vec<String> {
+    let mut packs = std::fs::read_dir(PACK_DIR)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "toml"))
+        .map(|p| p.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    packs.sort();
+    packs
+}
@@ -1,20 +1,126 @@
+use crate::util::syllabify;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Formatter;
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct NameRecord {
-    name: String,
+    pub(crate) name: String,
     #[serde(rename = "clean name")]
-    clean_name: String,
-    syllables: String,
-    count: usize,
+    pub(crate) clean_name: String,
+    pub(crate) syllables: String,
+    pub(crate) count: usize,
     #[serde(rename = "first part")]
-    first_part: Option<usize>,
-    gender: char,
+    pub(crate) first_part: Option<usize>,
+    pub(crate) gender: char,
+    #[serde(default)]
+    pub(crate) tribe: Option<String>,
+    #[serde(default)]
+    pub(crate) source: Option<String>,
+    #[serde(default)]
+    pub(crate) expansion: Option<String>,
+    /// Free-form tags, separated by `;` like the `names` column of the output files.
+    #[serde(default)]
+    pub(crate) tags: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl NameRecord {
+    /// Builds a corpus row for a name that has not been syllabified by hand yet.
+    /// Apostrophes and hyphens in `clean_name` mark the first part boundary.
+    pub fn candidate(name: &str, clean_name: &str, gender: char) -> Self {
+        let mut chunks = clean_name
+            .split(['\'', '-'])
+            .map(syllabify)
+            .filter(|s| !s.is_empty());
+        let first = chunks.next().unwrap_or_default();
+        let rest = chunks.flatten().collect::<Vec<_>>();
+        let first_part = (!rest.is_empty()).then_some(first.len());
+        let syllables = first.into_iter().chain(rest).collect::<Vec<_>>();
+        Self {
+            name: name.to_owned(),
+            clean_name: clean_name.to_owned(),
+            count: syllables.len(),
+            syllables: syllables.join("."),
+            first_part,
+            gender,
+            tribe: None,
+            source: None,
+            expansion: None,
+            tags: None,
+        }
+    }
+}
+
+/// Reads every row of a corpus file such as `data/syllables.csv`.
+///
+/// # Errors
+/// Fails if the file cannot be opened or a row does not match [`NameRecord`].
+pub fn read_records(path: &str) -> csv::Result<Vec<NameRecord>> {
+    csv::Reader::from_path(path)?.deserialize().collect()
+}
+
+/// Rewrites a corpus file with every column of [`NameRecord`].
+fn write_records<'a>(
+    path: &str,
+    records: impl IntoIterator<Item = &'a NameRecord>,
+) -> csv::Result<()> {
+    let mut wtr = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::Any(b'\n'))
+        .from_path(path)?;
+    for r in records {
+        wtr.serialize(r)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Appends rows to a corpus file in the columns its header names.
+///
+/// The rows the file already has are left untouched, unless it lacks a column of
+/// [`NameRecord`] like `source` in an older corpus. Then it is rewritten with all of
+/// them so no value is lost.
+///
+/// # Errors
+/// Fails if the file cannot be read or written.
+pub fn append_records(path: &str, records: &[NameRecord]) -> csv::Result<()> {
+    let columns = csv::Reader::from_path(path)?.headers()?.clone();
+    let mut serialized = csv::Writer::from_writer(vec![]);
+    for r in records {
+        serialized.serialize(r)?;
+    }
+    let serialized = serialized
+        .into_inner()
+        .map_err(|e| csv::Error::from(e.into_error()))?;
+    let mut rows = csv::Reader::from_reader(serialized.as_slice());
+    let headers = rows.headers()?.clone();
+    if headers.iter().any(|h| !columns.iter().any(|c| c == h)) {
+        let existing = read_records(path)?;
+        return write_records(path, existing.iter().chain(records));
+    }
+    let ends_with_newline = std::fs::read(path)?.last().is_none_or(|b| *b == b'\n');
+    let mut file = std::fs::OpenOptions::new().append(true).open(path)?;
+    if !ends_with_newline {
+        std::io::Write::write_all(&mut file, b"\n")?;
+    }
+    let mut wtr = csv::WriterBuilder::new()
+        .has_headers(false)
+        .terminator(csv::Terminator::Any(b'\n'))
+        .from_writer(file);
+    for row in rows.records() {
+        let row = row?;
+        wtr.write_record(columns.iter().map(|column| {
+            headers
+                .iter()
+                .position(|h| h == column)
+                .and_then(|i| row.get(i))
+                .unwrap_or_default()
+        }))?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Name {
     pub name: String,
     pub clean_name: String,
@@ -22,6 +128,14 @@ pub struct Name {
     pub guaranteed_parts: Vec<PartEntry>,
     pub possible_parts: Vec<PartEntry>,
     pub gender: char,
+    #[serde(default)]
+    pub tribe: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub expansion: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 impl Name {
@@ -47,7 +161,77 @@ impl Name {
             guaranteed_parts,
             possible_parts: vec![],
             gender: record.gender,
+            tribe: record.tribe,
+            source: record.source,
+            expansion: record.expansion,
+            tags: record
+                .tags
+                .iter()
+                .flat_map(|t| t.split(';'))
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    /// Every way a name without a known first part splits into a first and second part.
+    fn add_possible_parts(&mut self) {
+        if self.guaranteed_parts.len() > 0 {
+            return;
+        }
+        if self.syllables.len() == 1 {
+            self.possible_parts
+                .push(PartEntry::lone(self.syllables[0].clone(), 1));
+            return;
         }
+        for f in 1..self.syllables.len() {
+            let first = (&self.syllables[..f]).join("");
+            let second = (&self.syllables[f..]).join("");
+            self.possible_parts.push(PartEntry::first(first, f));
+            self.possible_parts
+                .push(PartEntry::second(second, self.syllables.len() - f));
+        }
+    }
+
+    /// Whether `label` names this entry's tribe or one of its tags (case-insensitive).
+    pub fn has_label(&self, label: &str) -> bool {
+        self.tribe
+            .iter()
+            .chain(self.tags.iter())
+            .any(|l| l.eq_ignore_ascii_case(label))
+    }
+}
+
+/// Every tribe and tag that occurs in the corpus, sorted for display.
+pub fn corpus_labels(names: &[Name]) -> Vec<String> {
+    let mut labels = names
+        .iter()
+        .flat_map(|n| n.tribe.iter().chain(n.tags.iter()))
+        .cloned()
+        .collect::<Vec<_>>();
+    labels.sort_unstable_by_key(|l| l.to_lowercase());
+    labels.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    labels
+}
+
+/// Restricts the names statistics are built from, by tribe or tag.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CorpusFilter {
+    /// If not empty, a name must carry at least one of these labels.
+    pub include: Vec<String>,
+    /// A name carrying any of these labels is dropped.
+    pub exclude: Vec<String>,
+}
+
+impl CorpusFilter {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn matches(&self, name: &Name) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|l| name.has_label(l)))
+            && !self.exclude.iter().any(|l| name.has_label(l))
     }
 }
 
@@ -84,51 +268,31 @@ impl PartEntry {
     }
 }
 
-pub fn generate_data() -> (Vec<OutputRecord>, Vec<OutputRecord>, Vec<Name>) {
-    let mut rdr = csv::Reader::from_path("data/syllables.csv".to_string()).unwrap();
-    let mut female_names = 0;
-    let mut male_names = 0;
-    let mut names = vec![];
-    for record in rdr.deserialize() {
-        let record: NameRecord = record.unwrap();
-        names.push(Name::from_record(record));
-    }
-    for n in &mut names {
-        if n.gender == 'm' {
-            male_names += 1;
-        } else {
-            female_names += 1;
-        }
-        if n.guaranteed_parts.len() > 0 {
-            continue;
-        }
-        if n.syllables.len() == 1 {
-            n.possible_parts
-                .push(PartEntry::lone(n.syllables[0].clone(), 1));
-            continue;
-        }
-        for f in 1..n.syllables.len() {
-            let first = (&n.syllables[..f]).join("");
-            let second = (&n.syllables[f..]).join("");
-            n.possible_parts.push(PartEntry::first(first, f));
-            n.possible_parts
-                .push(PartEntry::second(second, n.syllables.len() - f));
-        }
+/// The names a syllable starts, ends and is in the middle of, and its male and female counts.
+type SyllableTally = (Vec<usize>, Vec<usize>, Vec<usize>, f32, f32);
+
+/// The names a part starts and ends with their weights, and its male and female counts.
+type PartTally = (Vec<(usize, f32)>, Vec<(usize, f32)>, f32, f32);
+
+/// How much a name of `gender` counts as male and as female, unknown genders count half.
+fn gender_shares(gender: char) -> (f32, f32) {
+    match gender {
+        'm' => (1., 0.),
+        'f' => (0., 1.),
+        _ => (0.5, 0.5),
     }
+}
+
+fn tally_syllables(names: &[Name]) -> HashMap<String, SyllableTally> {
     let mut by_syllable = HashMap::new();
     for (idx, n) in names.iter().enumerate() {
+        let (male, female) = gender_shares(n.gender);
         for (i, s) in n.syllables.iter().enumerate() {
             let entry = by_syllable
                 .entry(s.clone())
                 .or_insert((vec![], vec![], vec![], 0., 0.));
-            if n.gender == 'm' {
-                entry.3 += 1.;
-            } else if n.gender == 'f' {
-                entry.4 += 1.;
-            } else {
-                entry.3 += 0.5;
-                entry.4 += 0.5;
-            }
+            entry.3 += male;
+            entry.4 += female;
             if i == 0 {
                 entry.0.push(idx);
             } else if i == n.syllables.len() - 1 {
@@ -138,26 +302,19 @@ pub fn generate_data() -> (Vec<OutputRecord>, Vec<OutputRecord>, Vec<Name>) {
             }
         }
     }
-    let mut syllable_occurrence = by_syllable
-        .iter()
-        .map(|(s, v)| (s.clone(), v.0.len(), v.1.len(), v.2.len(), v.3, v.4))
-        .collect::<Vec<_>>();
-    syllable_occurrence.sort_by_key(|a| a.1);
-    syllable_occurrence.reverse();
+    by_syllable
+}
+
+fn tally_parts(names: &[Name]) -> HashMap<(String, usize), PartTally> {
     let mut by_part = HashMap::new();
     for (i, n) in names.iter().enumerate() {
+        let (male, female) = gender_shares(n.gender);
         for p in &n.guaranteed_parts {
             let entry = by_part
                 .entry((p.value.clone(), p.len))
                 .or_insert((vec![], vec![], 0., 0.));
-            if n.gender == 'm' {
-                entry.2 += 1.;
-            } else if n.gender == 'f' {
-                entry.3 += 1.;
-            } else {
-                entry.2 += 0.5;
-                entry.3 += 0.5;
-            }
+            entry.2 += male;
+            entry.3 += female;
             match p.position {
                 0 => entry.0.push((i, 1.)),
                 1 => entry.1.push((i, 1.)),
@@ -171,14 +328,8 @@ pub fn generate_data() -> (Vec<OutputRecord>, Vec<OutputRecord>, Vec<Name>) {
             let entry = by_part
                 .entry((p.value.clone(), p.len))
                 .or_insert((vec![], vec![], 0., 0.));
-            if n.gender == 'm' {
-                entry.2 += 1.;
-            } else if n.gender == 'f' {
-                entry.3 += 1.;
-            } else {
-                entry.2 += 0.5;
-                entry.3 += 0.5;
-            }
+            entry.2 += male;
+            entry.3 += female;
             let v = 2. / (n.possible_parts.len() as f32);
             match p.position {
                 0 => entry.0.push((i, v)),
@@ -190,6 +341,40 @@ pub fn generate_data() -> (Vec<OutputRecord>, Vec<OutputRecord>, Vec<Name>) {
             }
         }
     }
+    by_part
+}
+
+/// Builds the syllable and part statistics of the corpus file at `path`.
+///
+/// # Errors
+/// Fails if the corpus file cannot be read.
+pub fn generate_data(
+    path: &str,
+    filter: &CorpusFilter,
+) -> csv::Result<(Vec<OutputRecord>, Vec<OutputRecord>, Vec<Name>)> {
+    let mut female_names = 0;
+    let mut male_names = 0;
+    let mut names = read_records(path)?
+        .into_iter()
+        .map(Name::from_record)
+        .filter(|n| filter.matches(n))
+        .collect::<Vec<_>>();
+    for n in &mut names {
+        if n.gender == 'm' {
+            male_names += 1;
+        } else {
+            female_names += 1;
+        }
+        n.add_possible_parts();
+    }
+    let by_syllable = tally_syllables(&names);
+    let mut syllable_occurrence = by_syllable
+        .iter()
+        .map(|(s, v)| (s.clone(), v.0.len(), v.1.len(), v.2.len(), v.3, v.4))
+        .collect::<Vec<_>>();
+    syllable_occurrence.sort_by_key(|a| a.1);
+    syllable_occurrence.reverse();
+    let by_part = tally_parts(&names);
     let part_occurrence = by_part
         .iter()
         .map(|(s, v)| {
@@ -204,9 +389,9 @@ pub fn generate_data() -> (Vec<OutputRecord>, Vec<OutputRecord>, Vec<Name>) {
         })
         .collect::<Vec<_>>();
 
-    let gender_ratio = male_names as f32 / female_names as f32;
+    // A filtered corpus may contain no female names at all.
+    let gender_ratio = male_names as f32 / (female_names as f32).max(1.);
 
-    let mut wtr = csv::Writer::from_path("syllable_data.csv".to_string()).unwrap();
     let mut syllable_records = vec![];
     for (s, first, second, middle, male, female) in &syllable_occurrence {
         let female = female * gender_ratio;
@@ -233,12 +418,9 @@ pub fn generate_data() -> (Vec<OutputRecord>, Vec<OutputRecord>, Vec<Name>) {
             names: names.join(";"),
             gender_ratio,
         };
-        wtr.serialize(&record).unwrap();
         syllable_records.push(record);
     }
 
-    wtr.flush().unwrap();
-    let mut wtr = csv::Writer::from_path("word_data.csv".to_string()).unwrap();
     let mut part_records = vec![];
     for (s, i, first, second, male, female) in &part_occurrence {
         let female = female * gender_ratio;
@@ -264,11 +446,145 @@ pub fn generate_data() -> (Vec<OutputRecord>, Vec<OutputRecord>, Vec<Name>) {
             names: names.join(";"),
             gender_ratio,
         };
-        wtr.serialize(&record).unwrap();
         part_records.push(record);
     }
-    wtr.flush().unwrap();
-    (syllable_records, part_records, names)
+    Ok((syllable_records, part_records, names))
+}
+
+/// Writes the statistics of `corpus` to `syllable_data.csv` and `word_data.csv` for inspection.
+///
+/// # Errors
+/// Fails if either file cannot be written.
+pub fn write_data(corpus: &Corpus) -> csv::Result<()> {
+    for (path, segments) in [
+        ("syllable_data.csv", &corpus.syllables),
+        ("word_data.csv", &corpus.parts),
+    ] {
+        let mut wtr = csv::Writer::from_path(path)?;
+        for segment in segments {
+            wtr.serialize(OutputRecord::from(segment))?;
+        }
+        wtr.flush()?;
+    }
+    Ok(())
+}
+
+/// The corpus the app and the command line tool start with.
+pub const DEFAULT_CORPUS: &str = "data/syllables.csv";
+
+/// A corpus file together with the statistics built from it.
+#[derive(Serialize, Deserialize)]
+pub struct Corpus {
+    pub label: String,
+    pub path: String,
+    pub names: Vec<Name>,
+    pub syllables: Vec<NameSegment>,
+    pub parts: Vec<NameSegment>,
+}
+
+impl Corpus {
+    /// Loads `path` restricted to `filter`. If no name matches, the whole file is used
+    /// and the second value is `true`.
+    ///
+    /// # Errors
+    /// Fails if the corpus file cannot be read.
+    pub fn load(label: &str, path: &str, filter: &CorpusFilter) -> csv::Result<(Self, bool)> {
+        let (mut s, mut p, mut n) = generate_data(path, filter)?;
+        let fell_back = n.is_empty() && !filter.is_empty();
+        if fell_back {
+            (s, p, n) = generate_data(path, &CorpusFilter::default())?;
+        }
+        let segments = |records: &[OutputRecord]| {
+            records
+                .iter()
+                .map(|r| NameSegment {
+                    corpus: label.to_owned(),
+                    ..r.into()
+                })
+                .collect::<Vec<_>>()
+        };
+        let corpus = Self {
+            label: label.to_owned(),
+            path: path.to_owned(),
+            syllables: segments(&s),
+            parts: segments(&p),
+            names: n,
+        };
+        Ok((corpus, fell_back))
+    }
+}
+
+/// How much one corpus contributes to a blend, by label.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlendWeight {
+    pub corpus: String,
+    pub weight: f32,
+}
+
+/// Scales the positional values of `segments` so their total is `share` of `total`.
+fn scaled_segments(segments: &[NameSegment], share: f32, total: f32) -> Vec<NameSegment> {
+    let mass = segments
+        .iter()
+        .map(|s| s.positional_data.overall)
+        .sum::<f32>();
+    let factor = if mass > 0. { share * total / mass } else { 0. };
+    segments
+        .iter()
+        .map(|s| {
+            let mut s = s.clone();
+            let d = &mut s.positional_data;
+            (d.overall, d.start, d.middle, d.end) = (
+                d.overall * factor,
+                d.start * factor,
+                d.middle * factor,
+                d.end * factor,
+            );
+            s
+        })
+        .collect()
+}
+
+/// Mixes the statistics of several corpora.
+///
+/// Each corpus contributes its weight's share of the combined segment mass, independent of
+/// its size; corpora without a weight are left out. Without any positive weight the first
+/// corpus is used on its own.
+pub fn blend_corpora(
+    corpora: &[Corpus],
+    weights: &[BlendWeight],
+) -> (Vec<NameSegment>, Vec<NameSegment>, Vec<Name>) {
+    let mut weighted = corpora
+        .iter()
+        .filter_map(|c| {
+            weights
+                .iter()
+                .find(|w| w.corpus == c.label)
+                .filter(|w| w.weight > 0.)
+                .map(|w| (c, w.weight))
+        })
+        .collect::<Vec<_>>();
+    if weighted.is_empty() {
+        weighted.extend(corpora.first().map(|c| (c, 1.)));
+    }
+    let weight_sum = weighted.iter().map(|(_, w)| w).sum::<f32>();
+    let mass = |segments: fn(&Corpus) -> &Vec<NameSegment>| {
+        weighted
+            .iter()
+            .flat_map(|(c, _)| segments(c))
+            .map(|s| s.positional_data.overall)
+            .sum::<f32>()
+    };
+    let (syllable_mass, part_mass) = (mass(|c| &c.syllables), mass(|c| &c.parts));
+    let mut syllables = vec![];
+    let mut parts = vec![];
+    let mut names = vec![];
+    for (c, w) in weighted {
+        let share = w / weight_sum;
+        syllables.extend(scaled_segments(&c.syllables, share, syllable_mass));
+        parts.extend(scaled_segments(&c.parts, share, part_mass));
+        names.extend(c.names.iter().cloned());
+    }
+    (syllables, parts, names)
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -283,7 +599,7 @@ pub struct OutputRecord {
     pub gender_ratio: f32,
 }
 
-#[derive(Copy, Clone, Default, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
 pub struct PositionalData {
     pub overall: f32,
     pub start: f32,
@@ -291,44 +607,79 @@ pub struct PositionalData {
     pub end: f32,
 }
 
-#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum SegmentKind {
     Part,
     Syllable,
     Apostrophe,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct NameSegment {
     pub segment_kind: SegmentKind,
     pub str: String,
     pub derived_names: Vec<String>,
     pub positional_data: PositionalData,
     pub gender_ratio: f32,
+    /// Label of the corpus this segment was taken from.
+    #[serde(default)]
+    pub corpus: String,
+    /// How the segment is written in a generated name, `str` itself if `None`.
+    #[serde(default)]
+    pub spelling: Option<String>,
 }
 
 impl NameSegment {
     pub fn apostrophe() -> NameSegment {
+        Self::joiner("'")
+    }
+
+    /// The text a culture puts between two parts, see [`crate::culture::CulturePack::joiner`].
+    pub fn joiner(text: &str) -> Self {
         Self {
             segment_kind: SegmentKind::Apostrophe,
-            str: "'".to_string(),
+            str: text.to_owned(),
             derived_names: vec![],
             positional_data: PositionalData::default(),
             gender_ratio: 0.,
+            corpus: String::new(),
+            spelling: None,
+        }
+    }
+
+    /// A segment the corpus has never seen, with no positional data and a neutral gender.
+    pub fn unknown(segment_kind: SegmentKind, text: &str) -> Self {
+        Self {
+            segment_kind,
+            gender_ratio: 0.5,
+            ..Self::joiner(text)
         }
     }
+
+    /// The letters of the segment as written in a name.
+    pub fn written(&self) -> &str {
+        self.spelling.as_deref().unwrap_or(&self.str)
+    }
 }
 
 impl std::fmt::Display for NameSegment {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self.segment_kind {
-                SegmentKind::Apostrophe => "'",
-                _ => self.str.as_str(),
-            }
-        )
+        write!(f, "{}", self.str)
+    }
+}
+
+impl From<&NameSegment> for OutputRecord {
+    fn from(segment: &NameSegment) -> Self {
+        Self {
+            segment_kind: segment.segment_kind,
+            str: segment.str.clone(),
+            overall_val: segment.positional_data.overall,
+            start_val: segment.positional_data.start,
+            middle_val: segment.positional_data.middle,
+            end_val: segment.positional_data.end,
+            names: segment.derived_names.join(";"),
+            gender_ratio: segment.gender_ratio,
+        }
     }
 }
 
@@ -345,6 +696,8 @@ impl From<&OutputRecord> for NameSegment {
                 end: record.end_val,
             },
             gender_ratio: record.gender_ratio,
+            corpus: String::new(),
+            spelling: None,
         }
     }
 }
@@ -0,0 +1,93 @@
+use crate::constraints::ConstraintError;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// The bundled list, see the file for the syntax of the entries.
+pub const DEFAULT_DENY_LIST: &str = include_str!("../data/deny_list.txt");
+
+/// Words generated names must not contain.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DenyList {
+    /// Whether the entries of [`DEFAULT_DENY_LIST`] are used.
+    pub use_default: bool,
+    /// Additional entries in the same syntax as the bundled list.
+    pub custom: String,
+}
+
+impl Default for DenyList {
+    fn default() -> Self {
+        Self {
+            use_default: true,
+            custom: String::new(),
+        }
+    }
+}
+
+enum Entry {
+    Substring(String),
+    Word(String),
+    Regex(Regex),
+}
+
+/// The compiled entries of a [`DenyList`].
+pub(crate) struct DenyFilter {
+    entries: Vec<Entry>,
+}
+
+fn entries(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+}
+
+/// Number of entries in a list text.
+pub fn entry_count(text: &str) -> usize {
+    entries(text).count()
+}
+
+impl DenyList {
+    /// # Errors
+    /// Fails on an invalid `regex:` entry.
+    pub(crate) fn filter(&self) -> Result<DenyFilter, ConstraintError> {
+        let default = if self.use_default {
+            DEFAULT_DENY_LIST
+        } else {
+            ""
+        };
+        let entries = entries(default)
+            .chain(entries(&self.custom))
+            .map(|line| {
+                Ok(if let Some(pattern) = line.strip_prefix("regex:") {
+                    let regex = Regex::new(pattern)
+                        .map_err(|e| ConstraintError::Regex(pattern.to_owned(), e))?;
+                    Entry::Regex(regex)
+                } else if let Some(word) = line.strip_prefix("word:") {
+                    Entry::Word(word.to_lowercase())
+                } else {
+                    Entry::Substring(line.to_lowercase())
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(DenyFilter { entries })
+    }
+}
+
+impl DenyFilter {
+    pub(crate) fn denies(&self, name: &str) -> bool {
+        let lower = name.to_lowercase();
+        let letters = lower
+            .chars()
+            .filter(|c| c.is_alphabetic())
+            .collect::<String>();
+        self.entries.iter().any(|entry| match entry {
+            Entry::Substring(text) => {
+                lower.contains(text.as_str()) || letters.contains(text.as_str())
+            }
+            Entry::Word(word) => {
+                letters == *word || lower.split(|c: char| !c.is_alphabetic()).any(|w| w == word)
+            }
+            Entry::Regex(regex) => regex.is_match(&lower) || regex.is_match(&letters),
+        })
+    }
+}
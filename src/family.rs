@@ -0,0 +1,256 @@
+use crate::config::GeneratorConfig;
+use crate::constraints::ConstraintError;
+use crate::culture::CulturePack;
+use crate::data_processing::{NameSegment, SegmentKind};
+use crate::joiners::JoinerStyle;
+use crate::name_gen::{
+    CorpusModels, GeneratedName, NameGenOptions, PinnedParts, generate_names_from_parts,
+};
+use crate::pronunciation::Pronunciation;
+use crate::util::capitalize;
+use serde::{Deserialize, Serialize};
+use std::fmt::Formatter;
+
+/// How the names of a family are related.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FamilyKind {
+    /// Every name starts with the same part.
+    #[default]
+    Siblings,
+    /// Every name ends with the same part.
+    Clan,
+    /// Every name starts with the part its predecessor ended with.
+    Lineage,
+}
+
+impl FamilyKind {
+    pub const ALL: [Self; 3] = [Self::Siblings, Self::Clan, Self::Lineage];
+
+    pub fn text(self) -> &'static str {
+        match self {
+            Self::Siblings => "siblings",
+            Self::Clan => "clan",
+            Self::Lineage => "lineage",
+        }
+    }
+}
+
+impl std::str::FromStr for FamilyKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.text() == s)
+            .ok_or_else(|| format!("unknown family kind `{s}`, expected siblings, clan or lineage"))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FamilyOptions {
+    pub kind: FamilyKind,
+    pub size: usize,
+}
+
+impl Default for FamilyOptions {
+    fn default() -> Self {
+        Self {
+            kind: FamilyKind::default(),
+            size: 4,
+        }
+    }
+}
+
+pub struct FamilyMember {
+    pub name: GeneratedName,
+    /// Indices of the elements shared with other members.
+    pub shared: Vec<usize>,
+    pub pronunciation: Pronunciation,
+}
+
+impl FamilyMember {
+    /// The elements of the name, written like in the name, each with whether it is shared.
+    pub fn segments(&self) -> impl Iterator<Item = (String, bool)> + '_ {
+        self.name
+            .styled_elements()
+            .into_iter()
+            .enumerate()
+            .map(|(i, text)| (text, self.shared.contains(&i)))
+    }
+}
+
+/// A group of related names.
+pub struct Family {
+    pub kind: FamilyKind,
+    pub members: Vec<FamilyMember>,
+    /// Why fewer members than asked for were generated, or how often the deny-list triggered.
+    pub report: Option<String>,
+}
+
+#[derive(Serialize)]
+struct MemberExport<'a> {
+    name: String,
+    segments: Vec<&'a str>,
+    style: JoinerStyle,
+    shared: &'a [usize],
+    pronunciation: &'a Pronunciation,
+}
+
+#[derive(Serialize)]
+struct FamilyExport<'a> {
+    kind: FamilyKind,
+    members: Vec<MemberExport<'a>>,
+}
+
+impl Family {
+    /// The whole family as one JSON object.
+    ///
+    /// # Errors
+    /// Fails only if `serde_json` cannot serialize the names.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        let members = self
+            .members
+            .iter()
+            .map(|m| MemberExport {
+                name: m.name.to_string(),
+                segments: m.name.elements.iter().map(|e| e.str.as_str()).collect(),
+                style: m.name.style,
+                shared: &m.shared,
+                pronunciation: &m.pronunciation,
+            })
+            .collect();
+        serde_json::to_string_pretty(&FamilyExport {
+            kind: self.kind,
+            members,
+        })
+    }
+}
+
+/// One name per line with the shared segments in brackets, like `[Zul]'jin`.
+impl std::fmt::Display for Family {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}:", capitalize(self.kind.text()))?;
+        for member in &self.members {
+            for (text, shared) in member.segments() {
+                if shared {
+                    write!(f, "[{text}]")?;
+                } else {
+                    write!(f, "{text}")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn last_part(name: &GeneratedName) -> Option<&NameSegment> {
+    name.elements
+        .iter()
+        .rev()
+        .find(|e| e.segment_kind != SegmentKind::Apostrophe)
+}
+
+/// Generates a family of `family.size` names that share parts as [`FamilyKind`] describes.
+///
+/// Siblings and clans pin the first or last part of a name drawn beforehand, a lineage
+/// draws one name after the other and pins the first part to the last one of its parent.
+///
+/// # Errors
+/// Fails like [`generate_names_from_parts`].
+pub fn generate_family(
+    parts: &[NameSegment],
+    syllables: &[NameSegment],
+    models: &CorpusModels,
+    settings: &NameGenOptions,
+    pack: &CulturePack,
+    config: &GeneratorConfig,
+    family: &FamilyOptions,
+) -> Result<Family, ConstraintError> {
+    let mut reports = vec![];
+    let mut generate = |amount: usize, pinned: PinnedParts| {
+        let settings = NameGenOptions {
+            amount,
+            length: settings.length.max(2.),
+            pinned,
+            ..settings.clone()
+        };
+        let generation =
+            generate_names_from_parts(parts, syllables, models, &settings, pack, config)?;
+        reports.extend(generation.report());
+        Ok::<_, ConstraintError>(generation.names)
+    };
+    let mut members = vec![];
+    match family.kind {
+        FamilyKind::Siblings | FamilyKind::Clan => {
+            let founder = generate(1, PinnedParts::default())?;
+            let shared = founder.first().and_then(|name| match family.kind {
+                FamilyKind::Siblings => name.elements.first(),
+                _ => last_part(name),
+            });
+            if let Some(shared) = shared {
+                let pinned = if family.kind == FamilyKind::Siblings {
+                    PinnedParts {
+                        first: Some(shared.clone()),
+                        last: None,
+                    }
+                } else {
+                    PinnedParts {
+                        first: None,
+                        last: Some(shared.clone()),
+                    }
+                };
+                for name in generate(family.size, pinned)? {
+                    let index = if family.kind == FamilyKind::Siblings {
+                        0
+                    } else {
+                        name.elements.len().saturating_sub(1)
+                    };
+                    members.push(FamilyMember {
+                        pronunciation: models.pronouncer.pronounce(&name),
+                        name,
+                        shared: vec![index],
+                    });
+                }
+            }
+        }
+        FamilyKind::Lineage => {
+            let mut inherited = None;
+            for _ in 0..family.size {
+                let pinned = PinnedParts {
+                    first: inherited.take(),
+                    last: None,
+                };
+                let has_parent = pinned.first.is_some();
+                let Some(name) = generate(1, pinned)?.pop() else {
+                    break;
+                };
+                inherited = last_part(&name).cloned();
+                let mut shared = vec![];
+                if has_parent {
+                    shared.push(0);
+                }
+                members.push(FamilyMember {
+                    pronunciation: models.pronouncer.pronounce(&name),
+                    name,
+                    shared,
+                });
+            }
+            // Every member but the youngest passes its last part down.
+            let count = members.len();
+            for member in members.iter_mut().take(count.saturating_sub(1)) {
+                member
+                    .shared
+                    .push(member.name.elements.len().saturating_sub(1));
+            }
+        }
+    }
+    reports.dedup();
+    Ok(Family {
+        kind: family.kind,
+        members,
+        report: (!reports.is_empty()).then(|| reports.join("\n")),
+    })
+}
@@ -0,0 +1,338 @@
+use crate::data_processing::Name;
+use crate::phonemes::PhonemeRules;
+use std::collections::HashMap;
+use std::fmt::Formatter;
+
+/// Folds of the cross-validation in [`GenderClassifier::cross_validate`].
+pub const DEFAULT_FOLDS: usize = 5;
+
+/// How far from the requested ratio strict gender mode accepts names by default.
+pub const DEFAULT_GENDER_TOLERANCE: f32 = 0.25;
+
+/// Index of the male and female counts.
+const MALE: usize = 0;
+const FEMALE: usize = 1;
+
+/// The gender lean of a name on the scale of `gender_ratio`, 1 male and 0 female.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GenderPrediction {
+    /// Probability that the name is male.
+    pub lean: f32,
+    /// How far the lean is from undecided, 0 to 1, lowered by the share of the name's
+    /// features the corpus never showed.
+    pub confidence: f32,
+}
+
+impl GenderPrediction {
+    pub fn is_male(&self) -> bool {
+        self.lean > 0.5
+    }
+}
+
+/// "73% male, confidence 0.46".
+impl std::fmt::Display for GenderPrediction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (share, gender) = if self.is_male() {
+            (self.lean, "male")
+        } else {
+            (1. - self.lean, "female")
+        };
+        write!(
+            f,
+            "{:.0}% {gender}, confidence {:.2}",
+            share * 100.,
+            self.confidence
+        )
+    }
+}
+
+/// Naive Bayes over the syllables of a name, where they stand and how it begins and ends,
+/// trained on the names of the corpus with a known gender.
+#[derive(Clone, Debug, Default)]
+pub struct GenderClassifier {
+    phonemes: PhonemeRules,
+    /// Male and female occurrences of every feature.
+    features: HashMap<String, [usize; 2]>,
+    /// Features seen in male and female names.
+    feature_totals: [usize; 2],
+    names: [usize; 2],
+}
+
+/// How well the classifier predicts names it was not trained on.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GenderReport {
+    pub folds: usize,
+    /// Names with a known gender that were predicted.
+    pub names: usize,
+    /// Share of all names predicted right.
+    pub accuracy: f32,
+    /// Share of the male and of the female names predicted right.
+    pub male_accuracy: f32,
+    pub female_accuracy: f32,
+    /// Accuracy of always guessing the more common gender, for comparison.
+    pub baseline: f32,
+}
+
+impl std::fmt::Display for GenderReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}-fold cross-validation on {} names:",
+            self.folds, self.names
+        )?;
+        writeln!(f, "  accuracy {:.1}%", self.accuracy * 100.)?;
+        writeln!(
+            f,
+            "  male names {:.1}%, female names {:.1}%",
+            self.male_accuracy * 100.,
+            self.female_accuracy * 100.
+        )?;
+        write!(
+            f,
+            "  always guessing the more common gender {:.1}%",
+            self.baseline * 100.
+        )
+    }
+}
+
+fn class(name: &Name) -> Option<usize> {
+    match name.gender {
+        'm' => Some(MALE),
+        'f' => Some(FEMALE),
+        _ => None,
+    }
+}
+
+/// The last or first `count` letters of a word.
+fn letters(word: &[char], count: usize, end: bool) -> String {
+    let count = count.min(word.len());
+    let range = if end {
+        word.len() - count..word.len()
+    } else {
+        0..count
+    };
+    word.get(range).unwrap_or_default().iter().collect()
+}
+
+impl GenderClassifier {
+    /// Counts the features of every corpus name with a known gender.
+    pub fn train(names: &[Name], phonemes: &PhonemeRules) -> Self {
+        let mut classifier = Self {
+            phonemes: phonemes.clone(),
+            ..Self::default()
+        };
+        for name in names {
+            if let Some(class) = class(name) {
+                classifier.add(&name.clean_name, class);
+            }
+        }
+        classifier
+    }
+
+    fn add(&mut self, name: &str, class: usize) {
+        let features = self.features_of(name);
+        self.count(&features, class, true);
+    }
+
+    /// Adds the features of one name to the counts of its class, or takes them away again.
+    fn count(&mut self, features: &[String], class: usize, added: bool) {
+        let step = |value: &mut usize, by: usize| {
+            *value = if added {
+                *value + by
+            } else {
+                value.saturating_sub(by)
+            };
+        };
+        if let (Some(names), Some(total)) = (
+            self.names.get_mut(class),
+            self.feature_totals.get_mut(class),
+        ) {
+            step(names, 1);
+            step(total, features.len());
+        }
+        for feature in features {
+            let counts = self.features.entry(feature.clone()).or_default();
+            if let Some(count) = counts.get_mut(class) {
+                step(count, 1);
+            }
+            if *counts == [0, 0] {
+                self.features.remove(feature);
+            }
+        }
+    }
+
+    /// The syllables of the name alone and by position, its first and last letters and
+    /// its syllable count.
+    fn features_of(&self, name: &str) -> Vec<String> {
+        let syllables = self.phonemes.syllabify(name);
+        let word = name
+            .to_lowercase()
+            .chars()
+            .filter(|c| c.is_alphabetic())
+            .collect::<Vec<_>>();
+        let last = syllables.len().saturating_sub(1);
+        let mut features = syllables
+            .iter()
+            .enumerate()
+            .flat_map(|(i, syllable)| {
+                let position = match i {
+                    0 => "first",
+                    i if i == last => "last",
+                    _ => "middle",
+                };
+                [
+                    format!("syllable:{syllable}"),
+                    format!("{position}:{syllable}"),
+                ]
+            })
+            .collect::<Vec<_>>();
+        features.extend([
+            format!("begins:{}", letters(&word, 2, false)),
+            format!("ends:{}", letters(&word, 1, true)),
+            format!("ends:{}", letters(&word, 2, true)),
+            format!("ends:{}", letters(&word, 3, true)),
+            format!("syllables:{}", syllables.len()),
+        ]);
+        features
+    }
+
+    /// The gender lean of any name, generated or not.
+    pub fn predict(&self, name: &str) -> GenderPrediction {
+        self.predict_features(&self.features_of(name))
+    }
+
+    fn predict_features(&self, features: &[String]) -> GenderPrediction {
+        let vocabulary = self.features.len().max(1) as f64;
+        let all_names = self.names.iter().sum::<usize>() as f64;
+        let mut log_odds = [0., 0.];
+        for (class, odds) in log_odds.iter_mut().enumerate() {
+            let names = self.names.get(class).copied().unwrap_or_default() as f64;
+            let total = self.feature_totals.get(class).copied().unwrap_or_default() as f64;
+            *odds = ((names + 1.) / (all_names + 2.)).ln();
+            for feature in features {
+                let count = self
+                    .features
+                    .get(feature)
+                    .and_then(|counts| counts.get(class))
+                    .copied()
+                    .unwrap_or_default() as f64;
+                *odds += ((count + 1.) / (total + vocabulary)).ln();
+            }
+        }
+        let [male, female] = log_odds;
+        let lean = 1. / (1. + (female - male).exp());
+        let seen = features
+            .iter()
+            .filter(|f| self.features.contains_key(*f))
+            .count();
+        let coverage = seen as f64 / features.len().max(1) as f64;
+        GenderPrediction {
+            lean: lean as f32,
+            confidence: ((2. * lean - 1.).abs() * coverage) as f32,
+        }
+    }
+
+    /// Names with a known gender, the most folds [`Self::cross_validate`] can use.
+    pub fn labelled(names: &[Name]) -> usize {
+        names.iter().filter(|n| class(n).is_some()).count()
+    }
+
+    /// Trains on all folds but one and predicts the names of that fold, for every fold.
+    /// Names are dealt to the folds in turn, so the result does not change between runs.
+    /// `folds` is kept between 2 and the number of [`Self::labelled`] names.
+    pub fn cross_validate(names: &[Name], phonemes: &PhonemeRules, folds: usize) -> GenderReport {
+        let mut classifier = Self {
+            phonemes: phonemes.clone(),
+            ..Self::default()
+        };
+        let labelled = names
+            .iter()
+            .filter_map(|n| Some((classifier.features_of(&n.clean_name), class(n)?)))
+            .collect::<Vec<_>>();
+        let folds = folds.min(labelled.len()).max(2);
+        for (features, class) in &labelled {
+            classifier.count(features, *class, true);
+        }
+        let mut right = [0, 0];
+        let mut counts = [0, 0];
+        // Naive Bayes only counts, so a fold is left out by taking its counts away.
+        for fold in 0..folds {
+            let held_out = labelled
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| i % folds == fold)
+                .map(|(_, name)| name)
+                .collect::<Vec<_>>();
+            for (features, class) in &held_out {
+                classifier.count(features, *class, false);
+            }
+            for (features, class) in &held_out {
+                let predicted = if classifier.predict_features(features).is_male() {
+                    MALE
+                } else {
+                    FEMALE
+                };
+                if let (Some(count), Some(right)) = (counts.get_mut(*class), right.get_mut(*class))
+                {
+                    *count += 1;
+                    *right += usize::from(predicted == *class);
+                }
+            }
+            for (features, class) in &held_out {
+                classifier.count(features, *class, true);
+            }
+        }
+        let share = |part: usize, whole: usize| part as f32 / whole.max(1) as f32;
+        let [male, female] = counts;
+        let [male_right, female_right] = right;
+        GenderReport {
+            folds,
+            names: male + female,
+            accuracy: share(male_right + female_right, male + female),
+            male_accuracy: share(male_right, male),
+            female_accuracy: share(female_right, female),
+            baseline: share(male.max(female), male + female),
+        }
+    }
+}
+
+/// The gender leans a batch of names ended up with.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct GenderDistribution {
+    pub mean: f32,
+    pub min: f32,
+    pub max: f32,
+    /// Names leaning male and female.
+    pub male: usize,
+    pub female: usize,
+}
+
+impl GenderDistribution {
+    pub fn of(leans: &[f32]) -> Self {
+        if leans.is_empty() {
+            return Self::default();
+        }
+        let male = leans.iter().filter(|lean| **lean > 0.5).count();
+        Self {
+            mean: leans.iter().sum::<f32>() / leans.len() as f32,
+            min: leans.iter().copied().fold(1., f32::min),
+            max: leans.iter().copied().fold(0., f32::max),
+            male,
+            female: leans.len() - male,
+        }
+    }
+}
+
+/// "mean lean 0.12 (0.01 to 0.24), 0 male and 5 female", or "no names" for an empty batch.
+impl std::fmt::Display for GenderDistribution {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.male + self.female == 0 {
+            return write!(f, "no names");
+        }
+        write!(
+            f,
+            "mean lean {:.2} ({:.2} to {:.2}), {} male and {} female",
+            self.mean, self.min, self.max, self.male, self.female
+        )
+    }
+}
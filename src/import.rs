@@ -0,0 +1,324 @@
+use crate::data_processing::NameRecord;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt::Formatter;
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(std::io::Error),
+    Csv(csv::Error),
+    Json(serde_json::Error),
+    MissingListview(&'static str),
+    MissingColumn(String),
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "could not read file: {e}"),
+            Self::Csv(e) => write!(f, "invalid csv: {e}"),
+            Self::Json(e) => write!(f, "invalid listview data: {e}"),
+            Self::MissingListview(template) => {
+                write!(f, "page contains no \"{template}\" listview")
+            }
+            Self::MissingColumn(column) => write!(f, "table has no column \"{column}\""),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<std::io::Error> for ImportError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<csv::Error> for ImportError {
+    fn from(e: csv::Error) -> Self {
+        Self::Csv(e)
+    }
+}
+
+impl From<serde_json::Error> for ImportError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+/// Provenance written to the `source` column of every imported row.
+pub const IMPORTED_SOURCE: &str = "imported";
+
+/// A corpus row proposed by an importer, together with the metadata the
+/// source offered. Serializes to the `data/syllables.csv` columns plus `race`.
+#[derive(Debug, Serialize)]
+pub struct ImportCandidate {
+    name: String,
+    #[serde(rename = "clean name")]
+    clean_name: String,
+    syllables: String,
+    count: usize,
+    #[serde(rename = "first part")]
+    first_part: Option<usize>,
+    gender: char,
+    tribe: Option<String>,
+    source: Option<String>,
+    expansion: Option<String>,
+    tags: Option<String>,
+    race: Option<String>,
+}
+
+impl ImportCandidate {
+    fn new(record: NameRecord, race: Option<String>) -> Self {
+        Self {
+            name: record.name,
+            clean_name: record.clean_name,
+            syllables: record.syllables,
+            count: record.count,
+            first_part: record.first_part,
+            gender: record.gender,
+            tribe: record.tribe,
+            source: Some(IMPORTED_SOURCE.to_owned()),
+            expansion: record.expansion,
+            tags: record.tags,
+            race,
+        }
+    }
+
+    /// Drops the importer-only metadata so the row can be appended to a corpus.
+    pub fn into_record(self) -> NameRecord {
+        NameRecord {
+            name: self.name,
+            clean_name: self.clean_name,
+            syllables: self.syllables,
+            count: self.count,
+            first_part: self.first_part,
+            gender: self.gender,
+            tribe: self.tribe,
+            source: self.source,
+            expansion: self.expansion,
+            tags: self.tags,
+        }
+    }
+}
+
+/// Title words learned from the corpus: every word of a full name that did not
+/// make it into its clean name, e.g. "warmother" or "ma'da".
+pub fn known_titles(records: &[NameRecord]) -> HashSet<String> {
+    let mut titles = HashSet::new();
+    for r in records {
+        let clean = r.clean_name.to_lowercase();
+        for word in r.name.split([' ', ',']).filter(|w| !w.is_empty()) {
+            let word = word.to_lowercase();
+            if !clean.split(' ').any(|c| c == word) {
+                titles.insert(word);
+            }
+        }
+    }
+    titles
+}
+
+/// Lowercase clean names already in the corpus, used to skip duplicate candidates.
+pub fn existing_names(records: &[NameRecord]) -> HashSet<String> {
+    records
+        .iter()
+        .map(|r| r.clean_name.to_lowercase())
+        .collect()
+}
+
+fn is_connector(word: &str) -> bool {
+    matches!(word.to_lowercase().as_str(), "the" | "of" | "de")
+}
+
+/// Strips titles and epithets from a full name ("Amaka the Crawg Ma'da" -> "Amaka").
+///
+/// Epithets follow "the", but "of" and "de" usually follow a descriptor and lead to the
+/// proper name ("Vision of Vol'jin" -> "Vol'jin"), unless a name with an apostrophe
+/// comes first ("Zul'jin of the Amani" -> "Zul'jin").
+pub fn clean_name(full_name: &str, titles: &HashSet<String>) -> Option<String> {
+    let head = full_name.split(',').next().unwrap_or_default();
+    let words = head.split_whitespace().collect::<Vec<_>>();
+    let connector = words
+        .iter()
+        .skip(1)
+        .position(|w| is_connector(w))
+        .map(|i| i + 1);
+    let words = match connector.and_then(|i| words.split_at_checked(i)) {
+        Some((before, after))
+            if after
+                .first()
+                .is_some_and(|w| !w.eq_ignore_ascii_case("the"))
+                && after.get(1).is_some_and(|w| !is_connector(w))
+                && !before.iter().any(|w| w.contains('\'')) =>
+        {
+            let after = after.get(1..).unwrap_or_default();
+            let end = after.iter().position(|w| is_connector(w));
+            after.get(..end.unwrap_or(after.len())).unwrap_or_default()
+        }
+        Some((before, _)) => before,
+        None => words.as_slice(),
+    };
+    let remaining = words
+        .iter()
+        .filter(|w| !titles.contains(&w.to_lowercase()))
+        .collect::<Vec<_>>();
+    remaining
+        .iter()
+        .find(|w| w.contains('\''))
+        .or(remaining.first())
+        .or(words.last().as_ref())
+        .map(|w| {
+            let w = w.trim_matches(|c: char| !c.is_alphabetic());
+            w.strip_suffix("'s").unwrap_or(w).to_owned()
+        })
+        .filter(|w| !w.is_empty())
+}
+
+/// Saved pages are usually the JSON-escaped response body; plain HTML is passed through.
+fn unescape_page(content: &str) -> String {
+    if content.contains("\\\"") {
+        let body = content.trim().trim_matches('"');
+        if let Ok(s) = serde_json::from_str::<String>(&format!("\"{body}\"")) {
+            return s;
+        }
+    }
+    content.to_owned()
+}
+
+fn listview_data(page: &str, template: &'static str) -> Result<Vec<Value>, ImportError> {
+    let marker = format!("template: \"{template}\"");
+    let listview = page
+        .find(&marker)
+        .and_then(|i| page.get(i..))
+        .ok_or(ImportError::MissingListview(template))?;
+    let key = listview
+        .split_once("WH.getPageData(\"")
+        .and_then(|(_, rest)| rest.split_once('"'))
+        .map(|(key, _)| key)
+        .ok_or(ImportError::MissingListview(template))?;
+    let script = format!("id=\"data.{key}\">");
+    let data = page
+        .split_once(&script)
+        .and_then(|(_, rest)| rest.split_once("</script>"))
+        .map(|(data, _)| data)
+        .ok_or(ImportError::MissingListview(template))?;
+    Ok(serde_json::from_str(data)?)
+}
+
+fn metadata(entry: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|k| entry.get(*k))
+        .find_map(|v| match v {
+            Value::String(s) if !s.is_empty() => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        })
+}
+
+fn gender_char(value: Option<&str>) -> char {
+    match value.map(str::to_lowercase).as_deref() {
+        Some("0" | "m" | "male") => 'm',
+        Some("1" | "f" | "female") => 'f',
+        _ => '?',
+    }
+}
+
+/// Parses the NPC results of a saved Wowhead search page into corpus candidates.
+/// Names whose clean form is already in `existing` (lowercase) are skipped.
+///
+/// # Errors
+/// Fails if the page has no NPC listview or its data block is not valid JSON.
+pub fn wowhead_candidates(
+    content: &str,
+    titles: &HashSet<String>,
+    existing: &mut HashSet<String>,
+) -> Result<Vec<ImportCandidate>, ImportError> {
+    let page = unescape_page(content);
+    let mut candidates = vec![];
+    for entry in listview_data(&page, "npc")? {
+        let Some(name) = metadata(&entry, &["name", "displayName"]) else {
+            continue;
+        };
+        let Some(clean) = clean_name(&name, titles) else {
+            continue;
+        };
+        if !existing.insert(clean.to_lowercase()) {
+            continue;
+        }
+        let gender = gender_char(metadata(&entry, &["gender", "sex"]).as_deref());
+        candidates.push(ImportCandidate::new(
+            NameRecord::candidate(&name, &clean, gender),
+            metadata(&entry, &["race", "raceName"]),
+        ));
+    }
+    Ok(candidates)
+}
+
+/// Which columns of a game-data table hold the name, race (or creature family) and gender.
+/// Columns are given by header name or by zero-based index.
+#[derive(Debug, Clone)]
+pub struct ColumnMapping {
+    pub name: String,
+    pub race: String,
+    pub gender: Option<String>,
+}
+
+fn column_index(headers: &csv::StringRecord, column: &str) -> Result<usize, ImportError> {
+    headers
+        .iter()
+        .position(|h| h.trim().eq_ignore_ascii_case(column.trim()))
+        .or_else(|| column.parse().ok().filter(|i| *i < headers.len()))
+        .ok_or_else(|| ImportError::MissingColumn(column.to_owned()))
+}
+
+/// Reads a locally exported creature table and keeps the rows whose race column
+/// contains `race_filter` (case-insensitive), e.g. "troll".
+///
+/// # Errors
+/// Fails if the table cannot be read or a mapped column does not exist.
+pub fn table_candidates<R: std::io::Read>(
+    table: R,
+    mapping: &ColumnMapping,
+    race_filter: &str,
+    titles: &HashSet<String>,
+    existing: &mut HashSet<String>,
+) -> Result<Vec<ImportCandidate>, ImportError> {
+    let mut rdr = csv::ReaderBuilder::new().flexible(true).from_reader(table);
+    let headers = rdr.headers()?.clone();
+    let name_col = column_index(&headers, &mapping.name)?;
+    let race_col = column_index(&headers, &mapping.race)?;
+    let gender_col = mapping
+        .gender
+        .as_deref()
+        .map(|c| column_index(&headers, c))
+        .transpose()?;
+    let race_filter = race_filter.to_lowercase();
+    let mut candidates = vec![];
+    for row in rdr.records() {
+        let row = row?;
+        let field = |col: Option<usize>| {
+            col.and_then(|c| row.get(c))
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+        let race = field(Some(race_col));
+        if !race.is_some_and(|r| r.to_lowercase().contains(&race_filter)) {
+            continue;
+        }
+        let Some(name) = field(Some(name_col)) else {
+            continue;
+        };
+        let Some(clean) = clean_name(name, titles) else {
+            continue;
+        };
+        if !existing.insert(clean.to_lowercase()) {
+            continue;
+        }
+        candidates.push(ImportCandidate::new(
+            NameRecord::candidate(name, &clean, gender_char(field(gender_col))),
+            race.map(str::to_owned),
+        ));
+    }
+    Ok(candidates)
+}
@@ -0,0 +1,222 @@
+use crate::data_processing::Name;
+use crate::orthography::syllable_ends;
+use crate::phonemes::PhonemeRules;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// How the joiners of a name are written.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JoinerStyle {
+    /// The joiner of the culture pack, as in "Zen'aliri".
+    #[default]
+    Plain,
+    /// The joiner with a capital letter after it, as in "Zen'Aliri".
+    Capitalized,
+    /// A hyphen instead of the joiner, as in "Zen-aliri".
+    Hyphenated,
+    /// No joiner at all, as in "Zenaliri".
+    Closed,
+}
+
+impl JoinerStyle {
+    pub const ALL: [Self; 4] = [
+        Self::Plain,
+        Self::Capitalized,
+        Self::Hyphenated,
+        Self::Closed,
+    ];
+
+    pub fn text(self) -> &'static str {
+        match self {
+            Self::Plain => "plain",
+            Self::Capitalized => "capitalized",
+            Self::Hyphenated => "hyphenated",
+            Self::Closed => "closed",
+        }
+    }
+
+    /// Writes one joiner, returning whether the next letter is capitalized.
+    pub(crate) fn write(self, joiner: &str, name: &mut String) -> bool {
+        match self {
+            Self::Plain => name.push_str(joiner),
+            Self::Capitalized => {
+                name.push_str(joiner);
+                return true;
+            }
+            Self::Hyphenated => name.push('-'),
+            Self::Closed => {}
+        }
+        false
+    }
+
+    /// The style of an existing name, judged by its first joiner and the letter after
+    /// it. `None` if the name has no joiner.
+    pub fn of(name: &str) -> Option<Self> {
+        let mut chars = name
+            .trim()
+            .chars()
+            .skip_while(|c| c.is_alphabetic())
+            .peekable();
+        let mut joiner = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_alphabetic()) {
+            joiner.push(c);
+        }
+        match chars.next() {
+            None => None,
+            Some(next) if next.is_uppercase() => Some(Self::Capitalized),
+            Some(_) if joiner == "-" => Some(Self::Hyphenated),
+            Some(_) => Some(Self::Plain),
+        }
+    }
+}
+
+impl std::str::FromStr for JoinerStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|style| style.text() == s)
+            .ok_or_else(|| {
+                format!("unknown style `{s}`, expected plain, capitalized, hyphenated or closed")
+            })
+    }
+}
+
+/// How often the corpus puts a joiner between the segments of a name.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct JoinerModel {
+    /// Share of names of two or more syllables with a joiner between their parts.
+    pub part: f32,
+    /// Share of the other syllable boundaries with a joiner, as in "Ren'ka'la".
+    pub syllable: f32,
+    /// Share of the corpus joiners that have a capital letter after them.
+    pub capitalized: f32,
+    /// Share of the corpus joiners that are hyphens.
+    pub hyphenated: f32,
+}
+
+impl Default for JoinerModel {
+    /// Always join the parts and never the syllables, like the generator used to.
+    fn default() -> Self {
+        Self {
+            part: 1.,
+            syllable: 0.,
+            capitalized: 0.,
+            hyphenated: 0.,
+        }
+    }
+}
+
+/// The joiners of a clean name, with the syllable boundary each one sits at and the style
+/// it is written in. The boundary is found by aligning the letters with the syllables of
+/// the corpus, or by syllabifying the text before the joiner if they do not align.
+fn joiners_of(name: &Name, joiner: &str, phonemes: &PhonemeRules) -> Vec<(usize, JoinerStyle)> {
+    let ends = syllable_ends(name);
+    let mut joiners = vec![];
+    let (mut letters, mut syllables) = (0, 0);
+    let mut rest = name.clean_name.trim();
+    while let Some((at, found)) = [joiner, "-"]
+        .into_iter()
+        .filter(|j| !j.is_empty())
+        .filter_map(|j| Some((rest.find(j)?, j)))
+        .min()
+    {
+        let Some((before, after)) = rest.split_at_checked(at) else {
+            break;
+        };
+        letters += before.chars().filter(|c| c.is_alphabetic()).count();
+        syllables += phonemes.syllabify(before).len();
+        rest = after.get(found.len()..).unwrap_or_default();
+        let boundary = ends.as_ref().map_or(syllables, |ends| {
+            ends.iter().filter(|end| **end <= letters).count()
+        });
+        let style = if found != joiner {
+            JoinerStyle::Hyphenated
+        } else if rest.starts_with(char::is_uppercase) {
+            JoinerStyle::Capitalized
+        } else {
+            JoinerStyle::Plain
+        };
+        joiners.push((boundary, style));
+    }
+    joiners
+}
+
+impl JoinerModel {
+    /// Counts the joiners and their styles in the clean names, titles like "Ma'da" are
+    /// left out.
+    ///
+    /// A joiner at the boundary the `first part` column of the corpus marks joins the
+    /// parts, any other one joins syllables. Names without a marked first part have
+    /// their part boundary at their first joiner, so "Mezil-kree" counts as joined parts
+    /// and two-part names written together like "Zalazane" lower the part share. The
+    /// styles are shares of the same joiners.
+    pub fn learn(names: &[Name], joiner: &str, phonemes: &PhonemeRules) -> Self {
+        let (mut parts, mut part_joiners) = (0, 0);
+        let (mut boundaries, mut syllable_joiners) = (0, 0);
+        let (mut capitalized, mut hyphenated) = (0, 0);
+        for name in names.iter().filter(|n| n.syllables.len() > 1) {
+            let joiners = joiners_of(name, joiner, phonemes);
+            let first_part = name
+                .guaranteed_parts
+                .iter()
+                .find(|p| p.position == 0)
+                .map(|p| p.len)
+                .or_else(|| joiners.first().map(|(boundary, _)| *boundary));
+            parts += 1;
+            boundaries += name.syllables.len() - 2;
+            for (boundary, style) in joiners {
+                if Some(boundary) == first_part {
+                    part_joiners += 1;
+                } else {
+                    syllable_joiners += 1;
+                }
+                match style {
+                    JoinerStyle::Capitalized => capitalized += 1,
+                    JoinerStyle::Hyphenated => hyphenated += 1,
+                    JoinerStyle::Plain | JoinerStyle::Closed => {}
+                }
+            }
+        }
+        if parts == 0 {
+            return Self::default();
+        }
+        let share = |count: usize, of: usize| {
+            if of == 0 {
+                0.
+            } else {
+                (count as f32 / of as f32).min(1.)
+            }
+        };
+        let joined = part_joiners + syllable_joiners;
+        Self {
+            part: share(part_joiners, parts),
+            syllable: share(syllable_joiners, boundaries),
+            capitalized: share(capitalized, joined),
+            hyphenated: share(hyphenated, joined),
+        }
+    }
+
+    /// Draws a style by how often the corpus uses it.
+    pub fn sample_style(&self, rng: &mut impl Rng) -> JoinerStyle {
+        let roll = rng.random::<f32>();
+        if roll < self.capitalized {
+            JoinerStyle::Capitalized
+        } else if roll < self.capitalized + self.hyphenated {
+            JoinerStyle::Hyphenated
+        } else {
+            JoinerStyle::Plain
+        }
+    }
+
+    /// The chances scaled by the apostrophe frequency setting, where 1 keeps them.
+    pub fn scaled(self, frequency: f32) -> Self {
+        Self {
+            part: (self.part * frequency).clamp(0., 1.),
+            syllable: (self.syllable * frequency).clamp(0., 1.),
+            ..self
+        }
+    }
+}
@@ -1,6 +1,20 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 pub mod app;
-mod data_processing;
-mod name_gen;
+pub mod config;
+pub mod constraints;
+pub mod culture;
+pub mod data_processing;
+pub mod deny_list;
+pub mod family;
+pub mod gender;
+pub mod import;
+pub mod joiners;
+pub mod name_gen;
+pub mod orthography;
+pub mod phonemes;
+pub mod phonotactics;
+pub mod pronunciation;
+pub mod speech;
 pub mod util;
+pub mod variants;
@@ -1,22 +1,30 @@
-use crate::data_processing::{Name, NameSegment, SegmentKind};
-use crate::util::{capitalize, ends_with_consonant, starts_with_consonant};
+use crate::config::{ConfigError, GenderWeights, GeneratorConfig, check};
+use crate::constraints::{ATTEMPTS_PER_NAME, Checker, ConstraintError, Constraints};
+use crate::culture::CulturePack;
+use crate::data_processing::{BlendWeight, CorpusFilter, Name, NameSegment, SegmentKind};
+use crate::deny_list::{DenyFilter, DenyList};
+use crate::gender::{GenderClassifier, GenderDistribution};
+use crate::joiners::{JoinerModel, JoinerStyle};
+use crate::orthography::Orthography;
+use crate::phonotactics::Phonotactics;
+use crate::pronunciation::Pronouncer;
+use crate::util::{capitalize, edit_distance, syllabify};
 use rand::Rng;
 use rand::distr::Distribution;
 use rand::distr::weighted::WeightedIndex;
+use rand::rngs::ThreadRng;
 use std::fmt::Formatter;
 
-const CUTOFF: f32 = 2.0;
-const VOWELS: [char; 5] = ['a', 'e', 'i', 'o', 'u'];
-const WEIGHT: f32 = 1.;
-
 fn generate_weights(
     list: &[&NameSegment],
     start: bool,
     end: bool,
     middle: bool,
-    gender_ratio: f32,
-) -> WeightedIndex<f32> {
-    WeightedIndex::new(list.iter().map(|segment| {
+    settings: &NameGenOptions,
+    config: &GeneratorConfig,
+    pack: &CulturePack,
+) -> Option<WeightedIndex<f32>> {
+    let weights = list.iter().map(|segment| {
         let mut value = 0.;
         let mut count: f32 = 0.;
         if start {
@@ -32,15 +40,98 @@ fn generate_weights(
             value += segment.positional_data.middle
         }
         count = count.max(1.);
-        let value = (value / count).powf(WEIGHT);
-        value * (1. - (gender_ratio - segment.gender_ratio).abs())
-    }))
-    .unwrap()
+        let value = (value / count).powf(config.weight);
+        let gender_match = 1. - (settings.gender_ratio - segment.gender_ratio).abs();
+        let reserved = if settings.omit_reserved {
+            pack.reserved_factor(&segment.str, start, end)
+        } else {
+            1.
+        };
+        value * gender_match.powf(config.gender_penalty) * reserved
+    });
+    WeightedIndex::new(settings.sampling.apply(weights.collect())).ok()
+}
+
+/// How adventurous the generator is when picking segments from a pool.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Sampling {
+    /// Below 1 the common segments dominate even more, above 1 rare ones catch up.
+    pub temperature: f32,
+    /// Only the `k` most likely segments of a pool are used.
+    pub top_k: Option<usize>,
+    /// Only the most likely segments that together make up this share of a pool are used.
+    pub top_p: Option<f32>,
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Self::from_creativity(0.5)
+    }
+}
+
+impl Sampling {
+    /// Maps a 0 to 1 slider onto the sampling parameters.
+    ///
+    /// 0.5 samples the weights as they are. Lower values cool the temperature down to 0.25
+    /// and cut off the unlikely tail, higher values heat it up to 4.
+    pub fn from_creativity(creativity: f32) -> Self {
+        let c = creativity.clamp(0., 1.);
+        Self {
+            temperature: 4_f32.powf(2. * c - 1.),
+            top_k: (c < 0.25).then(|| (10. + 80. * c).round() as usize),
+            top_p: (c < 0.5).then_some(0.5 + c),
+        }
+    }
+
+    /// The slider position matching the temperature.
+    pub fn creativity(&self) -> f32 {
+        f32::midpoint(self.temperature.log(4.), 1.).clamp(0., 1.)
+    }
+
+    /// Applies the temperature to the weights of a pool and zeroes everything cut off by
+    /// `top_k` and `top_p`. The most likely segment is always kept.
+    fn apply(&self, mut weights: Vec<f32>) -> Vec<f32> {
+        let total = weights.iter().sum::<f32>();
+        if total <= 0. {
+            return weights;
+        }
+        let exponent = 1. / self.temperature.max(0.01);
+        weights
+            .iter_mut()
+            .for_each(|w| *w = (*w / total).powf(exponent));
+        let mut order = weights.iter().copied().enumerate().collect::<Vec<_>>();
+        order.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        let mut keep = self.top_k.map_or(order.len(), |k| k.max(1));
+        if let Some(p) = self.top_p {
+            let threshold = p * weights.iter().sum::<f32>();
+            let mut cumulative = 0.;
+            let needed = order
+                .iter()
+                .take_while(|(_, w)| {
+                    let below = cumulative < threshold;
+                    cumulative += w;
+                    below
+                })
+                .count();
+            keep = keep.min(needed.max(1));
+        }
+        for (i, _) in order.into_iter().skip(keep) {
+            if let Some(w) = weights.get_mut(i) {
+                *w = 0.;
+            }
+        }
+        weights
+    }
 }
 
+#[derive(Clone)]
 pub struct GeneratedName {
     name: String,
     pub elements: Vec<NameSegment>,
+    /// How the joiners are written when baking.
+    pub style: JoinerStyle,
+    gender_weights: GenderWeights,
 }
 
 impl GeneratedName {
@@ -48,9 +139,30 @@ impl GeneratedName {
         Self {
             name: "".to_string(),
             elements: Vec::new(),
+            style: JoinerStyle::default(),
+            gender_weights: GenderWeights::default(),
         }
     }
 
+    /// A baked name made of the given elements.
+    pub fn from_elements(elements: Vec<NameSegment>, config: &GeneratorConfig) -> Self {
+        let mut name = Self {
+            name: String::new(),
+            elements,
+            style: JoinerStyle::default(),
+            gender_weights: config.gender_weights,
+        };
+        name.bake();
+        name
+    }
+
+    /// The same name baked with another style.
+    pub fn with_style(mut self, style: JoinerStyle) -> Self {
+        self.style = style;
+        self.bake();
+        self
+    }
+
     pub fn gender(&self) -> f32 {
         let mut count = 0;
         self.elements
@@ -64,20 +176,42 @@ impl GeneratedName {
             })
             .sum::<f32>()
             / (count as f32).max(1.)
-            * 0.1
-            + self.elements[0].gender_ratio * 0.45
-            + self.elements.last().unwrap().gender_ratio * 0.45
+            * self.gender_weights.average
+            + self.elements[0].gender_ratio * self.gender_weights.first
+            + self.elements.last().unwrap().gender_ratio * self.gender_weights.last
+    }
+
+    /// Syllables of all segments, the joiner does not count.
+    pub fn syllable_count(&self) -> usize {
+        self.elements
+            .iter()
+            .filter(|e| e.segment_kind != SegmentKind::Apostrophe)
+            .map(|e| syllabify(&e.str).len())
+            .sum()
+    }
+
+    /// The text each element has in the displayed name, styled as [`Self::style`] says.
+    pub fn styled_elements(&self) -> Vec<String> {
+        let mut capital = true;
+        self.elements
+            .iter()
+            .map(|element| {
+                let mut text = String::new();
+                if element.segment_kind == SegmentKind::Apostrophe {
+                    capital = self.style.write(&element.str, &mut text);
+                } else if capital {
+                    text = capitalize(element.written());
+                    capital = false;
+                } else {
+                    element.written().clone_into(&mut text);
+                }
+                text
+            })
+            .collect()
     }
 
     pub fn bake(&mut self) {
-        self.name = capitalize(
-            &self
-                .elements
-                .iter()
-                .map(|e| e.to_string())
-                .collect::<Vec<_>>()
-                .join(""),
-        );
+        self.name = self.styled_elements().concat();
     }
 }
 
@@ -87,12 +221,33 @@ impl std::fmt::Display for GeneratedName {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct NameGenOptions {
     pub amount: usize,
     pub omit_reserved: bool,
     pub length: f32,
     pub gender_ratio: f32,
+    /// Only names matching this filter feed the part and syllable statistics.
+    pub filter: CorpusFilter,
+    /// Share of each loaded corpus in the generated statistics.
+    pub blend: Vec<BlendWeight>,
+    pub sampling: Sampling,
+    pub constraints: Constraints,
+    pub deny_list: DenyList,
+    pub diversity: Diversity,
+    /// Scales how often joiners are used compared to the corpus, 1 keeps the corpus rate.
+    pub apostrophes: f32,
+    /// How joiners are written, `None` draws the style by its frequency in the corpus.
+    pub style: Option<JoinerStyle>,
+    /// Write segments the way the corpus spells them instead of as syllable keys.
+    pub spellings: bool,
+    /// Strict gender mode: only names the gender classifier places at most this far from
+    /// `gender_ratio` are kept. `None` leaves the gender to the segment weights.
+    pub gender_tolerance: Option<f32>,
+    /// Parts every name has to start or end with, used for families.
+    #[serde(skip)]
+    pub pinned: PinnedParts,
 }
 
 impl Default for NameGenOptions {
@@ -102,119 +257,571 @@ impl Default for NameGenOptions {
             omit_reserved: true,
             length: 2.2,
             gender_ratio: 1.,
+            filter: CorpusFilter::default(),
+            blend: vec![],
+            sampling: Sampling::default(),
+            constraints: Constraints::default(),
+            deny_list: DenyList::default(),
+            diversity: Diversity::default(),
+            apostrophes: 1.,
+            style: None,
+            spellings: true,
+            gender_tolerance: None,
+            pinned: PinnedParts::default(),
+        }
+    }
+}
+
+impl NameGenOptions {
+    /// # Errors
+    /// Names the first value that is out of range, like [`GeneratorConfig::validate`].
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if let Some(tolerance) = self.gender_tolerance {
+            check(
+                "gender_tolerance",
+                tolerance,
+                (0.0..=1.0).contains(&tolerance),
+                "between 0 and 1",
+            )?;
         }
+        Ok(())
     }
 }
 
+#[derive(Clone, Debug, Default)]
+pub struct PinnedParts {
+    pub first: Option<NameSegment>,
+    pub last: Option<NameSegment>,
+}
+
+/// How different the names of one batch have to be from each other.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Diversity {
+    /// No name appears twice, ignoring case.
+    pub unique: bool,
+    /// How many more names may use a segment that an earlier name used.
+    pub max_part_reuse: Option<usize>,
+    /// Minimum number of letter edits between any two names.
+    pub min_distance: Option<usize>,
+}
+
+impl Default for Diversity {
+    fn default() -> Self {
+        Self {
+            unique: true,
+            max_part_reuse: None,
+            min_distance: None,
+        }
+    }
+}
+
+impl PinnedParts {
+    fn contains(&self, segment: &NameSegment) -> bool {
+        [&self.first, &self.last]
+            .into_iter()
+            .flatten()
+            .any(|pinned| pinned.str.eq_ignore_ascii_case(&segment.str))
+    }
+
+    /// Writes the pinned parts of a respelled name the way they were written in the name
+    /// they were taken from, so every name sharing a part also shares its spelling.
+    fn keep_spellings(&self, name: &mut GeneratedName) {
+        if let (Some(pinned), Some(first)) = (&self.first, name.elements.first_mut()) {
+            first.spelling.clone_from(&pinned.spelling);
+        }
+        if let (Some(pinned), Some(last)) = (&self.last, name.elements.last_mut()) {
+            last.spelling.clone_from(&pinned.spelling);
+        }
+    }
+
+    /// The segments of a name that are not pinned, without joiners.
+    fn unpinned<'a>(&self, name: &'a GeneratedName) -> impl Iterator<Item = &'a NameSegment> {
+        name.elements
+            .iter()
+            .filter(|e| e.segment_kind != SegmentKind::Apostrophe && !self.contains(e))
+    }
+}
+
+impl Diversity {
+    /// Whether `name` differs enough from the batch. Pinned parts are shared on purpose,
+    /// so they neither count as reuse nor towards the distance between names.
+    fn allows(&self, name: &GeneratedName, batch: &[GeneratedName], pinned: &PinnedParts) -> bool {
+        let lower = name.name.to_lowercase();
+        let segments = |name: &GeneratedName| {
+            pinned
+                .unpinned(name)
+                .map(|e| e.str.to_lowercase())
+                .collect::<Vec<_>>()
+        };
+        let text = |name: &GeneratedName| {
+            if pinned.first.is_none() && pinned.last.is_none() {
+                name.name.to_lowercase()
+            } else {
+                pinned
+                    .unpinned(name)
+                    .map(|e| e.written().to_lowercase())
+                    .collect()
+            }
+        };
+        let reuses = |segment: &String| {
+            batch
+                .iter()
+                .filter(|other| segments(other).contains(segment))
+                .count()
+        };
+        let own_text = text(name);
+        let fits = |other: &GeneratedName| {
+            (!self.unique || other.name.to_lowercase() != lower)
+                && self
+                    .min_distance
+                    .is_none_or(|min| edit_distance(&text(other), &own_text) >= min)
+        };
+        batch.iter().all(fits)
+            && self
+                .max_part_reuse
+                .is_none_or(|max| segments(name).iter().all(|s| reuses(s) <= max))
+    }
+}
+
+/// The names of one generation run.
+pub struct Generation {
+    pub names: Vec<GeneratedName>,
+    /// The amount that was asked for.
+    pub wanted: usize,
+    /// Candidates drawn, including the ones that broke a constraint.
+    pub attempts: usize,
+    /// Candidates dropped because they broke a constraint.
+    pub rejected: usize,
+    /// Candidates replaced because they matched the deny-list.
+    pub denied: usize,
+    /// Candidates dropped because they were too close to an earlier name of the batch.
+    pub similar: usize,
+    /// Candidates dropped because a join the corpus does not use could not be repaired.
+    pub clashes: usize,
+    /// Candidates dropped in strict gender mode for leaning too far from the request.
+    pub off_gender: usize,
+    /// The gender leans of the names in strict gender mode.
+    pub genders: Option<GenderDistribution>,
+}
+
+impl Generation {
+    fn new(wanted: usize) -> Self {
+        Self {
+            names: vec![],
+            wanted,
+            attempts: 0,
+            rejected: 0,
+            denied: 0,
+            similar: 0,
+            clashes: 0,
+            off_gender: 0,
+            genders: None,
+        }
+    }
+
+    /// Keeps a baked candidate unless the deny-list, strict gender mode or the diversity
+    /// settings drop it, and counts why it was dropped. `lean` is its predicted gender
+    /// lean in strict gender mode.
+    fn accept_candidate(
+        &mut self,
+        name: GeneratedName,
+        lean: Option<f32>,
+        deny_filter: &DenyFilter,
+        settings: &NameGenOptions,
+    ) -> bool {
+        if deny_filter.denies(&name.name) {
+            self.denied += 1;
+        } else if let (Some(lean), Some(tolerance)) = (lean, settings.gender_tolerance)
+            && (lean - settings.gender_ratio).abs() > tolerance
+        {
+            self.off_gender += 1;
+        } else if !settings
+            .diversity
+            .allows(&name, &self.names, &settings.pinned)
+        {
+            self.similar += 1;
+        } else {
+            self.names.push(name);
+            return true;
+        }
+        false
+    }
+
+    /// Says how often the deny-list triggered and why fewer names than wanted were generated.
+    pub fn report(&self) -> Option<String> {
+        let mut lines = vec![];
+        if self.denied > 0 {
+            lines.push(format!("The deny-list replaced {} names.", self.denied));
+        }
+        if self.names.len() < self.wanted {
+            let reasons = [
+                (self.rejected, "broke the constraints"),
+                (self.similar, "were too similar to others"),
+                (self.clashes, "had joins the corpus does not use"),
+            ]
+            .into_iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, reason)| format!("{count} {reason}"))
+            .collect::<Vec<_>>();
+            let mut line = format!(
+                "Only {} of {} names met the constraints after {} attempts",
+                self.names.len(),
+                self.wanted,
+                self.attempts
+            );
+            if !reasons.is_empty() {
+                line.push_str(&format!(", {}", reasons.join(", ")));
+            }
+            line.push('.');
+            lines.push(line);
+        }
+        if let Some(genders) = &self.genders {
+            lines.push(format!(
+                "Gender: {genders}, {} candidates were dropped for their lean.",
+                self.off_gender
+            ));
+        }
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+}
+
+/// How many middle syllables are drawn when looking for one that fits between two segments.
+const BRIDGE_ATTEMPTS: usize = 20;
+
+/// Draws middle syllables until one joins both neighbours the way the corpus does.
+fn bridge<'a>(
+    middle: &[&'a NameSegment],
+    weights: &WeightedIndex<f32>,
+    left: &NameSegment,
+    right: &NameSegment,
+    phonotactics: &Phonotactics,
+    min_count: usize,
+    rng: &mut impl Rng,
+) -> Option<&'a NameSegment> {
+    (0..BRIDGE_ATTEMPTS)
+        .filter_map(|_| middle.get(weights.sample(rng)).copied())
+        .find(|s| {
+            phonotactics.allows(&left.str, &s.str, min_count)
+                && phonotactics.allows(&s.str, &right.str, min_count)
+        })
+}
+
+/// Bridges middle syllables into a two-part name, each after the first part or before the
+/// last one, while a chance that starts at `length` and falls off with every syllable
+/// holds. Returns where the first part ends now.
+fn insert_middle_syllables<'a>(
+    name: &mut GeneratedName,
+    length: f32,
+    config: &GeneratorConfig,
+    bridge: impl Fn(&NameSegment, &NameSegment, &mut ThreadRng) -> Option<&'a NameSegment>,
+    rng: &mut ThreadRng,
+) -> usize {
+    let mut start_pos = 0;
+    let mut end_pos = 1;
+    let mut syllable_insert = length;
+    while rng.random::<f32>() < syllable_insert {
+        let after_first = rng.random::<f32>() > 0.5;
+        let index = if after_first { start_pos + 1 } else { end_pos };
+        let elements = &name.elements;
+        if let (Some(left), Some(right)) = (elements.get(index - 1), elements.get(index))
+            && let Some(syl) = bridge(left, right, rng)
+        {
+            name.elements.insert(index, syl.clone());
+            if after_first {
+                start_pos += 1;
+                end_pos += 1;
+            }
+        }
+        let falloff = config.falloff_base + rng.random::<f32>() * config.falloff_spread;
+        syllable_insert /= falloff;
+    }
+    start_pos
+}
+
+/// Bridges the joins the corpus does not use, which pinned parts or a name without middle
+/// syllables may still have. Back to front so earlier indices stay valid. Returns where
+/// the first part ends now, or `None` if a join could not be bridged.
+fn repair_joins<'a>(
+    name: &mut GeneratedName,
+    mut start_pos: usize,
+    phonotactics: &Phonotactics,
+    config: &GeneratorConfig,
+    bridge: impl Fn(&NameSegment, &NameSegment, &mut ThreadRng) -> Option<&'a NameSegment>,
+    rng: &mut ThreadRng,
+) -> Option<usize> {
+    for index in (1..name.elements.len()).rev() {
+        let elements = &name.elements;
+        let (Some(left), Some(right)) = (elements.get(index - 1), elements.get(index)) else {
+            continue;
+        };
+        if phonotactics.allows(&left.str, &right.str, config.min_join_count)
+            || rng.random::<f32>() < config.cluster_allowance
+        {
+            continue;
+        }
+        let syl = bridge(left, right, rng)?;
+        name.elements.insert(index, syl.clone());
+        if index <= start_pos + 1 {
+            start_pos += 1;
+        }
+    }
+    Some(start_pos)
+}
+
+/// Puts joiners between the segments of a name by the chances of `joiners`, the
+/// boundary before `second_part` by the part chance. Joiners go in last, back to front so
+/// that boundary stays put.
+fn apply_joiners(
+    name: &mut GeneratedName,
+    second_part: usize,
+    joiners: &JoinerModel,
+    joiner: &str,
+    rng: &mut impl Rng,
+) {
+    for boundary in (1..name.elements.len()).rev() {
+        let chance = if boundary == second_part {
+            joiners.part
+        } else {
+            joiners.syllable
+        };
+        if rng.random::<f32>() < chance {
+            name.elements.insert(boundary, NameSegment::joiner(joiner));
+        }
+    }
+}
+
+/// Parts whose positional value clears `cutoff`, with their weights. A small, filtered
+/// corpus may have none that can be drawn, in which case every part seen in that
+/// position is used instead, or every part if none of those can be drawn either.
+fn above_cutoff<'a>(
+    parts: &[&'a NameSegment],
+    cutoff: f32,
+    value: impl Fn(&NameSegment) -> f32,
+    weights: impl Fn(&[&NameSegment]) -> Option<WeightedIndex<f32>>,
+) -> Option<(Vec<&'a NameSegment>, WeightedIndex<f32>)> {
+    [cutoff, 0.]
+        .into_iter()
+        .map(|cutoff| {
+            parts
+                .iter()
+                .filter(|o| value(o) > cutoff)
+                .copied()
+                .collect::<Vec<_>>()
+        })
+        .chain(std::iter::once(parts.to_vec()))
+        .find_map(|pool| {
+            let weights = weights(&pool)?;
+            Some((pool, weights))
+        })
+}
+
+/// The segments names are drawn from with their weights, narrowed to the constraints.
+struct Pools<'a> {
+    single: Vec<&'a NameSegment>,
+    single_weights: WeightedIndex<f32>,
+    first: Vec<&'a NameSegment>,
+    first_weights: WeightedIndex<f32>,
+    second: Vec<&'a NameSegment>,
+    second_weights: WeightedIndex<f32>,
+    middle: Vec<&'a NameSegment>,
+    /// `None` if a small corpus or the constraints left no middle syllable to draw.
+    middle_weights: Option<WeightedIndex<f32>>,
+}
+
+impl<'a> Pools<'a> {
+    /// Keeps the segments the constraints allow and weighs them for their position.
+    ///
+    /// # Errors
+    /// Fails if the constraints rule out every candidate of a part pool.
+    fn new(
+        parts: &'a [NameSegment],
+        syllables: &'a [NameSegment],
+        checker: &Checker,
+        settings: &NameGenOptions,
+        pack: &CulturePack,
+        config: &GeneratorConfig,
+    ) -> Result<Self, ConstraintError> {
+        let parts = parts
+            .iter()
+            .filter(|v| checker.allows_segment(&v.str))
+            .collect::<Vec<_>>();
+        let unsatisfiable = |pool: &str| {
+            ConstraintError::Unsatisfiable(format!("no {pool} in the corpus fits the constraints"))
+        };
+        let single = parts
+            .iter()
+            .filter(|v| checker.allows_first(&v.str))
+            .copied()
+            .collect::<Vec<_>>();
+        // A pool a small corpus or the constraints left without candidates yields `None`.
+        let weights = |list: &[&NameSegment], start, end, middle| {
+            generate_weights(list, start, end, middle, settings, config, pack)
+        };
+        let single_weights =
+            weights(&single, true, true, true).ok_or_else(|| unsatisfiable("part"))?;
+        let (first, first_weights) = above_cutoff(
+            &single,
+            config.start_cutoff,
+            |o| o.positional_data.start,
+            |list| weights(list, true, false, false),
+        )
+        .ok_or_else(|| unsatisfiable("first part"))?;
+        let (second, second_weights) = above_cutoff(
+            &parts,
+            config.end_cutoff,
+            |o| o.positional_data.end,
+            |list| weights(list, false, true, false),
+        )
+        .ok_or_else(|| unsatisfiable("second part"))?;
+        let middle = syllables
+            .iter()
+            .filter(|o| o.positional_data.middle > 0. && checker.allows_segment(&o.str))
+            .collect::<Vec<_>>();
+        let middle_weights = weights(&middle, false, false, true);
+        Ok(Self {
+            single,
+            single_weights,
+            first,
+            first_weights,
+            second,
+            second_weights,
+            middle,
+            middle_weights,
+        })
+    }
+}
+
+/// What generation learns from the names of a corpus. Learned once per corpus and culture
+/// pack and passed to every generation, since relearning them would slow down previews.
+#[derive(Clone, Debug, Default)]
+pub struct CorpusModels {
+    pub joiners: JoinerModel,
+    pub orthography: Orthography,
+    pub phonotactics: Phonotactics,
+    pub gender: GenderClassifier,
+    pub pronouncer: Pronouncer,
+}
+
+impl CorpusModels {
+    pub fn learn(names: &[Name], pack: &CulturePack) -> Self {
+        Self {
+            joiners: pack.joiner_model(names),
+            orthography: Orthography::learn(names),
+            phonotactics: Phonotactics::learn(names, &pack.phonemes),
+            gender: GenderClassifier::train(names, &pack.phonemes),
+            pronouncer: Pronouncer::learn(names, &pack.phonemes),
+        }
+    }
+}
+
+/// Generates `settings.amount` names, drawing at most [`ATTEMPTS_PER_NAME`] candidates
+/// per name when constraints, the deny-list, the diversity settings or strict gender
+/// mode reject some of them.
+///
+/// # Errors
+/// Fails if the constraints or the deny-list are invalid, or if the constraints
+/// rule out every candidate of a pool.
 pub fn generate_names_from_parts(
-    parts: &Vec<NameSegment>,
-    syllables: &Vec<NameSegment>,
-    _: &Vec<Name>,
+    parts: &[NameSegment],
+    syllables: &[NameSegment],
+    models: &CorpusModels,
     settings: &NameGenOptions,
-) -> Vec<GeneratedName> {
-    let reserved = vec!["jin", "fon", "zen", "zul"];
-    let parts = if settings.omit_reserved {
-        parts
-            .into_iter()
-            .filter(|v| !reserved.iter().any(|s| *s == v.str.as_str()))
-            .collect::<Vec<_>>()
-    } else {
-        parts.into_iter().collect::<Vec<_>>()
+    pack: &CulturePack,
+    config: &GeneratorConfig,
+) -> Result<Generation, ConstraintError> {
+    let joiners = models.joiners.scaled(settings.apostrophes);
+    let checker = settings.constraints.checker()?;
+    let deny_filter = settings.deny_list.filter()?;
+    let pools = Pools::new(parts, syllables, &checker, settings, pack, config)?;
+    let middle_weights = pools.middle_weights.as_ref();
+    let phonotactics = &models.phonotactics;
+    let bridge = |left: &NameSegment, right: &NameSegment, rng: &mut ThreadRng| {
+        bridge(
+            &pools.middle,
+            middle_weights?,
+            left,
+            right,
+            phonotactics,
+            config.min_join_count,
+            rng,
+        )
     };
-    let part_weights = generate_weights(&parts, true, true, true, settings.gender_ratio);
-    let first = parts
-        .iter()
-        .filter(|o| o.positional_data.start > CUTOFF)
-        .copied()
-        .collect::<Vec<_>>();
-    let first_weights = generate_weights(&first, true, false, false, settings.gender_ratio);
-    let second = parts
-        .iter()
-        .filter(|o| o.positional_data.end > CUTOFF)
-        .copied()
-        .collect::<Vec<_>>();
-    let second_weights = generate_weights(&second, false, true, false, settings.gender_ratio);
-    let middle = syllables
-        .iter()
-        .filter(|o| o.positional_data.middle > 0.)
-        .collect::<Vec<_>>();
-    let middle_weights = generate_weights(&middle, false, false, true, settings.gender_ratio);
-    let consonant_bounds = middle
-        .iter()
-        .map(|s| {
-            let cs = s.str.chars().collect::<Vec<char>>();
-            (
-                !VOWELS.contains(&cs[0]),
-                !VOWELS.contains(&cs[cs.len() - 1]),
-                s,
-            )
-        })
-        .collect::<Vec<_>>();
-    let open_start = consonant_bounds
-        .iter()
-        .filter(|(s, _, _)| !s)
-        .map(|(_, _, o)| **o)
-        .collect::<Vec<_>>();
-    let open_end = consonant_bounds
-        .iter()
-        .filter(|(_, e, _)| !e)
-        .map(|(_, _, o)| **o)
-        .collect::<Vec<_>>();
-    let open_start_weights =
-        generate_weights(&open_start, false, false, true, settings.gender_ratio);
-    let open_end_weights = generate_weights(&open_end, false, false, true, settings.gender_ratio);
-    let mut rng = rand::rng();
-    let mut generated_results = Vec::new();
-    for _ in 0..settings.amount {
+    let sample_name = |rng: &mut ThreadRng| {
         let mut generated_name = GeneratedName::new();
+        generated_name.gender_weights = config.gender_weights;
+        generated_name.style = settings.style.unwrap_or_else(|| joiners.sample_style(rng));
+        let pinned = &settings.pinned;
         let mut length = settings.length;
-        if length < 2. {
+        if length < 2. && pinned.first.is_none() && pinned.last.is_none() {
             if rng.random::<f32>() > (length - 1.) {
-                let result = parts[part_weights.sample(&mut rng)];
+                let result = pools.single[pools.single_weights.sample(rng)];
                 generated_name.elements.push(result.clone());
-                generated_results.push(generated_name);
-                continue;
+                return Some(generated_name);
             }
             length = 0.;
         } else {
             length -= 2.;
         }
-        let first = first[first_weights.sample(&mut rng)];
-        let second = second[second_weights.sample(&mut rng)];
+        let first = match &pinned.first {
+            Some(part) => part,
+            None => pools.first[pools.first_weights.sample(rng)],
+        };
+        let second = match &pinned.last {
+            Some(part) => part,
+            None => pools.second[pools.second_weights.sample(rng)],
+        };
         generated_name.elements.push(first.clone());
-        generated_name.elements.push(NameSegment::apostrophe());
         generated_name.elements.push(second.clone());
-        let mut start_pos = 0;
-        let mut end_pos = 2;
-        let mut syllable_insert = length;
-        while rng.random::<f32>() < syllable_insert {
-            let first_str = &generated_name.elements[start_pos].str;
-            let second_str = &generated_name.elements[start_pos].str;
-            let after_first = rng.random::<f32>() > 0.5;
-            let allow_consonant_clusters = rng.random::<f32>() < 0.05;
-            let syl = if after_first && ends_with_consonant(first_str) && !allow_consonant_clusters
-            {
-                open_start[open_start_weights.sample(&mut rng)]
-            } else if !after_first
-                && starts_with_consonant(&second_str)
-                && !allow_consonant_clusters
-            {
-                open_end[open_end_weights.sample(&mut rng)]
-            } else {
-                &syllables[middle_weights.sample(&mut rng)]
-            };
-            if after_first {
-                generated_name.elements.insert(start_pos + 1, syl.clone());
-                start_pos += 1;
-                end_pos += 1;
-            } else {
-                generated_name.elements.insert(end_pos, syl.clone());
-            }
-            let falloff = rng.random::<f32>() + 1.;
-            syllable_insert /= falloff;
+        let start_pos = match middle_weights {
+            Some(_) => insert_middle_syllables(&mut generated_name, length, config, bridge, rng),
+            None => 0,
+        };
+        let start_pos = repair_joins(
+            &mut generated_name,
+            start_pos,
+            phonotactics,
+            config,
+            bridge,
+            rng,
+        )?;
+        apply_joiners(
+            &mut generated_name,
+            start_pos + 1,
+            &joiners,
+            &pack.joiner,
+            rng,
+        );
+        Some(generated_name)
+    };
+    let mut generation = Generation::new(settings.amount);
+    let classifier = settings.gender_tolerance.map(|_| &models.gender);
+    let mut leans = vec![];
+    let mut rng = rand::rng();
+    while generation.names.len() < settings.amount
+        && generation.attempts < settings.amount * ATTEMPTS_PER_NAME
+    {
+        generation.attempts += 1;
+        let Some(mut name) = sample_name(&mut rng) else {
+            generation.clashes += 1;
+            continue;
+        };
+        if settings.spellings {
+            models.orthography.respell(&mut name, &mut rng);
+            settings.pinned.keep_spellings(&mut name);
+        }
+        name.bake();
+        if !checker.allows(&name.name, name.syllable_count()) {
+            generation.rejected += 1;
+            continue;
         }
-        generated_results.push(generated_name);
+        let lean = classifier.map(|classifier| classifier.predict(&name.name).lean);
+        if generation.accept_candidate(name, lean, &deny_filter, settings) {
+            leans.extend(lean);
+        }
+    }
+    if classifier.is_some() {
+        generation.genders = Some(GenderDistribution::of(&leans));
     }
-    generated_results.iter_mut().for_each(|n| n.bake());
-    generated_results
+    Ok(generation)
 }
@@ -0,0 +1,159 @@
+use crate::data_processing::{Name, SegmentKind};
+use crate::name_gen::GeneratedName;
+use crate::util::edit_distance;
+use rand::Rng;
+use rand::seq::IndexedRandom as _;
+use std::collections::HashMap;
+
+/// How many letters a piece may be longer than the syllable it is aligned with.
+const MAX_EXTRA_LETTERS: usize = 3;
+
+/// The ways the corpus writes each syllable and part, since the syllables are phonemic:
+/// "Cazex" is `ka.zex` and "Boatema" is `bwa.te.ma`.
+#[derive(Clone, Debug, Default)]
+pub struct Orthography {
+    spellings: HashMap<String, Vec<(String, usize)>>,
+}
+
+/// Splits `letters` into one piece per syllable with the fewest letter edits between
+/// the syllables and their pieces. `None` if the letters cannot be split that way.
+fn align(syllables: &[String], letters: &[char]) -> Option<Vec<String>> {
+    // For every number of letters used so far, the cheapest split and where its pieces end.
+    let mut best: Vec<Option<(usize, Vec<usize>)>> = vec![None; letters.len() + 1];
+    *best.first_mut()? = Some((0, vec![]));
+    for syllable in syllables {
+        let longest = syllable.chars().count() + MAX_EXTRA_LETTERS;
+        let mut next: Vec<Option<(usize, Vec<usize>)>> = vec![None; letters.len() + 1];
+        for (start, entry) in best.iter().enumerate() {
+            let Some((cost, ends)) = entry else {
+                continue;
+            };
+            for end in start + 1..=letters.len().min(start + longest) {
+                let piece = letters.get(start..end)?.iter().collect::<String>();
+                let cost = cost + edit_distance(syllable, &piece);
+                if let Some(slot) = next.get_mut(end)
+                    && slot.as_ref().is_none_or(|(best, _)| cost < *best)
+                {
+                    let mut ends = ends.clone();
+                    ends.push(end);
+                    *slot = Some((cost, ends));
+                }
+            }
+        }
+        best = next;
+    }
+    let (_, ends) = best.pop()??;
+    let mut start = 0;
+    ends.into_iter()
+        .map(|end| {
+            let piece = letters.get(start..end)?.iter().collect();
+            start = end;
+            Some(piece)
+        })
+        .collect()
+}
+
+/// How many letters of a clean name its first one, two, ... syllables span, found by
+/// aligning the letters with the syllables. `None` if they cannot be aligned.
+pub(crate) fn syllable_ends(name: &Name) -> Option<Vec<usize>> {
+    let letters = name
+        .clean_name
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .collect::<Vec<_>>();
+    let mut end = 0;
+    let ends = align(&name.syllables, &letters)?
+        .iter()
+        .map(|piece| {
+            end += piece.chars().count();
+            end
+        })
+        .collect();
+    Some(ends)
+}
+
+/// Letters the corpus writes a leading `k` with besides `k` itself, as in "Cazex".
+const K_SPELLINGS: [char; 2] = ['c', 'q'];
+
+/// Whether a piece is close enough to its syllable to count as a spelling of it. The
+/// piece has to start with the same letter, or write a leading `k` as `c` or `q`, so
+/// misaligned rows like "Tasha" for `ra.sha` do not teach that `ra` is written "ta".
+fn plausible(syllable: &str, piece: &str) -> bool {
+    let same_start = match (syllable.chars().next(), piece.chars().next()) {
+        (Some(s), Some(p)) => s == p || (s == 'k' && K_SPELLINGS.contains(&p)),
+        _ => false,
+    };
+    let longer = syllable.chars().count().max(piece.chars().count());
+    same_start && edit_distance(syllable, piece) * 2 <= longer
+}
+
+impl Orthography {
+    /// Aligns the letters of every clean name with its syllables. The spellings of a
+    /// part are those of its syllables, for every way the name can be split into parts.
+    pub fn learn(names: &[Name]) -> Self {
+        let mut orthography = Self::default();
+        for name in names {
+            let letters = name
+                .clean_name
+                .to_lowercase()
+                .chars()
+                .filter(|c| c.is_alphabetic())
+                .collect::<Vec<_>>();
+            let Some(pieces) = align(&name.syllables, &letters) else {
+                continue;
+            };
+            if !name
+                .syllables
+                .iter()
+                .zip(&pieces)
+                .all(|(syllable, piece)| plausible(syllable, piece))
+            {
+                continue;
+            }
+            for (syllable, piece) in name.syllables.iter().zip(&pieces) {
+                orthography.add(syllable, piece);
+            }
+            for split in 1..name.syllables.len() {
+                let (Some((first, second)), Some((first_spelled, second_spelled))) = (
+                    name.syllables.split_at_checked(split),
+                    pieces.split_at_checked(split),
+                ) else {
+                    continue;
+                };
+                orthography.add(&first.concat(), &first_spelled.concat());
+                orthography.add(&second.concat(), &second_spelled.concat());
+            }
+        }
+        orthography
+    }
+
+    fn add(&mut self, key: &str, spelling: &str) {
+        let spellings = self.spellings.entry(key.to_owned()).or_default();
+        match spellings.iter_mut().find(|(s, _)| s == spelling) {
+            Some((_, count)) => *count += 1,
+            None => spellings.push((spelling.to_owned(), 1)),
+        }
+    }
+
+    /// Every spelling of a syllable or part key with how often the corpus uses it.
+    pub fn spellings(&self, key: &str) -> &[(String, usize)] {
+        self.spellings.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    /// Draws a spelling for every segment of the name by how often the corpus uses it.
+    /// Segments the corpus never spells keep their key.
+    pub fn respell(&self, name: &mut GeneratedName, rng: &mut impl Rng) {
+        for element in &mut name.elements {
+            if element.segment_kind == SegmentKind::Apostrophe {
+                continue;
+            }
+            element.spelling = self
+                .spellings(&element.str)
+                .choose_weighted(rng, |(_, count)| *count)
+                .ok()
+                .filter(|(spelling, _)| *spelling != element.str)
+                .map(|(spelling, _)| spelling.clone());
+        }
+    }
+}
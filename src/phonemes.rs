@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation as _;
+
+/// Whether a grapheme is pronounced as a vowel or a consonant where it stands.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PhonemeClass {
+    Vowel,
+    Consonant,
+}
+
+/// How a culture's letters are grouped and classified, set in the `[phonemes]` table of a
+/// culture pack. Matching ignores case, accents have to be listed like any other letter.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PhonemeRules {
+    /// Letters that are always vowels.
+    pub vowels: String,
+    /// Letters that are consonants before a vowel and vowels otherwise, like the "y" of
+    /// "Yazma" and of "Kyl".
+    pub semivowels: String,
+    /// Letter groups that are one sound, like "kh" or "sh". Longer groups are matched first.
+    pub digraphs: Vec<String>,
+}
+
+impl Default for PhonemeRules {
+    fn default() -> Self {
+        Self {
+            vowels: "aeiouàáâäãåèéêëìíîïòóôöõøùúûüæœ".to_owned(),
+            semivowels: "y".to_owned(),
+            digraphs: ["ch", "gh", "kh", "sh", "th"].map(str::to_owned).to_vec(),
+        }
+    }
+}
+
+impl PhonemeRules {
+    /// Splits a lowercased text into user-perceived characters, joining digraphs.
+    /// Combining accents stay with their letter.
+    pub fn graphemes(&self, text: &str) -> Vec<String> {
+        let lower = text.to_lowercase();
+        let mut digraphs = self
+            .digraphs
+            .iter()
+            .map(|d| d.to_lowercase())
+            .filter(|d| !d.is_empty())
+            .collect::<Vec<_>>();
+        digraphs.sort_by_key(|d| std::cmp::Reverse(d.len()));
+        let mut graphemes = vec![];
+        let mut rest = lower.as_str();
+        while !rest.is_empty() {
+            let digraph = digraphs.iter().find(|d| rest.starts_with(d.as_str()));
+            let Some(grapheme) = digraph
+                .map(String::as_str)
+                .or_else(|| rest.graphemes(true).next())
+            else {
+                break;
+            };
+            graphemes.push(grapheme.to_owned());
+            rest = rest.get(grapheme.len()..).unwrap_or_default();
+        }
+        graphemes
+    }
+
+    fn base_is(letters: &str, grapheme: &str) -> bool {
+        grapheme
+            .chars()
+            .next()
+            .is_some_and(|c| letters.to_lowercase().contains(c))
+    }
+
+    /// The class of every grapheme, semivowels depending on the one after them.
+    pub fn classify(&self, graphemes: &[String]) -> Vec<PhonemeClass> {
+        let vowel = |g: &String| Self::base_is(&self.vowels, g);
+        let semivowel = |g: &String| Self::base_is(&self.semivowels, g);
+        graphemes
+            .iter()
+            .enumerate()
+            .map(|(i, g)| {
+                let before_vowel = graphemes
+                    .get(i + 1)
+                    .is_some_and(|next| vowel(next) || semivowel(next));
+                if vowel(g) || (semivowel(g) && !before_vowel) {
+                    PhonemeClass::Vowel
+                } else {
+                    PhonemeClass::Consonant
+                }
+            })
+            .collect()
+    }
+
+    /// The graphemes of a text with their classes.
+    pub fn phonemes(&self, text: &str) -> Vec<(String, PhonemeClass)> {
+        let graphemes = self.graphemes(text);
+        let classes = self.classify(&graphemes);
+        graphemes.into_iter().zip(classes).collect()
+    }
+
+    /// The single letters that are always vowels.
+    pub fn vowel_letters(&self) -> Vec<char> {
+        self.vowels.chars().flat_map(char::to_lowercase).collect()
+    }
+
+    /// Splits a single word into rough syllables the way the corpus writes them.
+    ///
+    /// Vowel runs form the nucleus, a lone consonant between two nuclei starts the
+    /// next syllable and longer clusters only hand their last consonant (or digraph) over.
+    pub fn syllabify(&self, word: &str) -> Vec<String> {
+        let letters = word
+            .chars()
+            .filter(|c| c.is_alphabetic() || is_combining(*c))
+            .collect::<String>();
+        let mut syllables: Vec<String> = vec![];
+        let mut current = String::new();
+        let mut cluster: Vec<String> = vec![];
+        let mut has_nucleus = false;
+        let mut phonemes = self.phonemes(&letters).into_iter().peekable();
+        while let Some((grapheme, class)) = phonemes.next() {
+            if class == PhonemeClass::Consonant {
+                cluster.push(grapheme);
+                continue;
+            }
+            if has_nucleus && !cluster.is_empty() {
+                let onset = cluster.pop().unwrap_or_default();
+                current.push_str(&cluster.concat());
+                syllables.push(std::mem::take(&mut current));
+                current.push_str(&onset);
+            } else {
+                current.push_str(&cluster.concat());
+            }
+            cluster.clear();
+            current.push_str(&grapheme);
+            while let Some((vowel, _)) = phonemes.next_if(|(_, c)| *c == PhonemeClass::Vowel) {
+                current.push_str(&vowel);
+            }
+            has_nucleus = true;
+        }
+        current.push_str(&cluster.concat());
+        if !current.is_empty() {
+            syllables.push(current);
+        }
+        syllables
+    }
+}
+
+/// Combining accents, which are not alphabetic on their own.
+fn is_combining(c: char) -> bool {
+    ('\u{300}'..='\u{36f}').contains(&c)
+}
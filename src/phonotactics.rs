@@ -0,0 +1,69 @@
+use crate::data_processing::Name;
+use crate::phonemes::PhonemeRules;
+use std::collections::HashMap;
+
+/// The consonant clusters and vowel sequences the corpus forms where two syllables meet.
+#[derive(Clone, Debug, Default)]
+pub struct Phonotactics {
+    phonemes: PhonemeRules,
+    joins: HashMap<String, usize>,
+}
+
+impl Phonotactics {
+    /// Counts the joins between neighbouring syllables of every name.
+    pub fn learn(names: &[Name], phonemes: &PhonemeRules) -> Self {
+        let mut phonotactics = Self {
+            phonemes: phonemes.clone(),
+            joins: HashMap::new(),
+        };
+        for name in names {
+            for pair in name.syllables.windows(2) {
+                if let [left, right] = pair
+                    && let Some(join) = phonotactics.join(left, right)
+                {
+                    *phonotactics.joins.entry(join).or_default() += 1;
+                }
+            }
+        }
+        phonotactics
+    }
+
+    /// The letters around the boundary if `left` ends and `right` starts alike: the
+    /// consonants of "rast" and "kha" form "stkh", the vowels of "ka" and "ai" form "aai".
+    /// A consonant meeting a vowel is no join, it is always allowed. Digraphs like "kh"
+    /// and semivowels like "y" are classified by the culture's [`PhonemeRules`].
+    fn join(&self, left: &str, right: &str) -> Option<String> {
+        let left = self.phonemes.graphemes(left);
+        let right = self.phonemes.graphemes(right);
+        // Classified together, so a semivowel at the end of `left` sees what follows it.
+        let graphemes = [left.as_slice(), right.as_slice()].concat();
+        let classes = self.phonemes.classify(&graphemes);
+        let (left_classes, right_classes) = classes.split_at_checked(left.len())?;
+        let class = *left_classes.last()?;
+        if *right_classes.first()? != class {
+            return None;
+        }
+        let tail = left_classes
+            .iter()
+            .rev()
+            .take_while(|c| **c == class)
+            .count();
+        let head = right_classes.iter().take_while(|c| **c == class).count();
+        let start = left.len() - tail;
+        Some(graphemes.get(start..left.len() + head)?.concat())
+    }
+
+    /// How often the corpus joins two syllables this way, `None` if they form no join.
+    pub fn count(&self, left: &str, right: &str) -> Option<usize> {
+        let join = self.join(left, right)?;
+        Some(self.joins.get(&join).copied().unwrap_or_default())
+    }
+
+    /// Whether `right` may follow `left`. A join has to be seen `min_count` times, so
+    /// one-off sequences like "aaa" are not allowed, while doubled letters like "kk" or
+    /// "zz" are if the corpus doubles them often enough.
+    pub fn allows(&self, left: &str, right: &str, min_count: usize) -> bool {
+        self.count(left, right)
+            .is_none_or(|count| count >= min_count)
+    }
+}
@@ -0,0 +1,227 @@
+use crate::data_processing::{Name, SegmentKind};
+use crate::name_gen::GeneratedName;
+use crate::phonemes::{PhonemeClass, PhonemeRules};
+use serde::Serialize;
+
+/// Consonants written differently in IPA or in an English respelling than in the names.
+const CONSONANTS: [(&str, &str, &str); 12] = [
+    ("kh", "x", "kh"),
+    ("gh", "ɣ", "gh"),
+    ("sh", "ʃ", "sh"),
+    ("ch", "tʃ", "ch"),
+    ("th", "θ", "th"),
+    ("zh", "ʒ", "zh"),
+    ("ph", "f", "f"),
+    ("j", "dʒ", "j"),
+    ("y", "j", "y"),
+    ("c", "k", "k"),
+    ("q", "k", "k"),
+    ("g", "ɡ", "g"),
+];
+
+/// Vowels and vowel runs as IPA, respelled in open and in closed syllables.
+const VOWELS: [(&str, &str, &str, &str); 13] = [
+    ("ai", "aɪ", "eye", "eye"),
+    ("ay", "aɪ", "eye", "eye"),
+    ("au", "aʊ", "ow", "ow"),
+    ("ei", "eɪ", "ay", "ay"),
+    ("oi", "ɔɪ", "oy", "oy"),
+    ("aa", "ɑː", "ah", "ah"),
+    ("ee", "iː", "ee", "ee"),
+    ("oo", "uː", "oo", "oo"),
+    ("a", "ɑ", "ah", "a"),
+    ("e", "ɛ", "eh", "e"),
+    ("i", "i", "ee", "i"),
+    ("o", "o", "oh", "o"),
+    ("u", "u", "oo", "u"),
+];
+
+/// How to say a name: an English-friendly respelling like "ZEN-tah-kee" with the stressed
+/// syllable in capitals, and an IPA transcription like "/ˈzɛn.tɑ.ki/".
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Pronunciation {
+    pub respelling: String,
+    pub ipa: String,
+}
+
+impl std::fmt::Display for Pronunciation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.respelling, self.ipa)
+    }
+}
+
+struct Syllable {
+    respelling: String,
+    ipa: String,
+    /// Has a coda or a long vowel, which draws the stress.
+    heavy: bool,
+}
+
+/// Transcribes syllables with a culture's [`PhonemeRules`] and places the stress like the
+/// corpus suggests.
+#[derive(Clone, Debug, Default)]
+pub struct Pronouncer {
+    phonemes: PhonemeRules,
+    /// For every syllable count, how often each position of a corpus name is heavy.
+    heavy_positions: Vec<Vec<usize>>,
+}
+
+fn consonant(grapheme: &str, initial: bool) -> (String, String) {
+    if grapheme == "x" {
+        // "Xan" starts like "zan", elsewhere x is "ks".
+        let sound = if initial { "z" } else { "ks" };
+        return (sound.to_owned(), sound.to_owned());
+    }
+    CONSONANTS
+        .iter()
+        .find(|(letters, _, _)| *letters == grapheme)
+        .map_or_else(
+            || (grapheme.to_owned(), grapheme.to_owned()),
+            |(_, ipa, respelling)| ((*ipa).to_owned(), (*respelling).to_owned()),
+        )
+}
+
+/// A vowel run as IPA and respelling, diphthongs first and otherwise vowel by vowel.
+fn vowels(run: &str, closed: bool) -> (String, String) {
+    let lookup = |letters: &str| {
+        VOWELS
+            .iter()
+            .find(|(v, ..)| *v == letters)
+            .map(|(_, ipa, open, shut)| (*ipa, if closed { *shut } else { *open }))
+    };
+    if let Some((ipa, respelling)) = lookup(run) {
+        return (ipa.to_owned(), respelling.to_owned());
+    }
+    let (mut ipa, mut respelling) = (String::new(), String::new());
+    for c in run.chars() {
+        let letter = c.to_string();
+        let (i, r) = lookup(&letter).unwrap_or((&letter, &letter));
+        ipa.push_str(i);
+        respelling.push_str(r);
+    }
+    (ipa, respelling)
+}
+
+impl Pronouncer {
+    /// Counts the heavy syllables of the corpus names by position and name length.
+    pub fn learn(names: &[Name], phonemes: &PhonemeRules) -> Self {
+        let mut pronouncer = Self {
+            phonemes: phonemes.clone(),
+            heavy_positions: vec![],
+        };
+        for name in names {
+            let count = name.syllables.len();
+            if pronouncer.heavy_positions.len() < count {
+                pronouncer.heavy_positions.resize(count, vec![]);
+            }
+            let heavy = name
+                .syllables
+                .iter()
+                .map(|s| pronouncer.syllable(s, true).heavy)
+                .collect::<Vec<_>>();
+            if let Some(positions) = pronouncer.heavy_positions.get_mut(count.wrapping_sub(1)) {
+                positions.resize(count, 0);
+                for (position, heavy) in positions.iter_mut().zip(heavy) {
+                    *position += usize::from(heavy);
+                }
+            }
+        }
+        pronouncer
+    }
+
+    fn syllable(&self, text: &str, initial: bool) -> Syllable {
+        let phonemes = self.phonemes.phonemes(text);
+        let nucleus_start = phonemes
+            .iter()
+            .position(|(_, class)| *class == PhonemeClass::Vowel)
+            .unwrap_or(phonemes.len());
+        let nucleus_end = phonemes
+            .iter()
+            .skip(nucleus_start)
+            .position(|(_, class)| *class == PhonemeClass::Consonant)
+            .map_or(phonemes.len(), |end| nucleus_start + end);
+        let closed = nucleus_end < phonemes.len();
+        let mut syllable = Syllable {
+            respelling: String::new(),
+            ipa: String::new(),
+            heavy: closed || nucleus_end - nucleus_start > 1,
+        };
+        let mut push = |(ipa, respelling): (String, String)| {
+            syllable.ipa.push_str(&ipa);
+            syllable.respelling.push_str(&respelling);
+        };
+        for (i, (grapheme, _)) in phonemes.iter().enumerate() {
+            if i == nucleus_start {
+                let run = phonemes
+                    .get(nucleus_start..nucleus_end)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(g, _)| g.as_str())
+                    .collect::<String>();
+                push(vowels(&run, closed));
+            } else if i < nucleus_start || i >= nucleus_end {
+                push(consonant(grapheme, initial && i == 0));
+            }
+        }
+        syllable
+    }
+
+    /// The stressed syllable: the position corpus names of the same length most often make
+    /// heavy, shifted to a heavy syllable of this name where one is as likely.
+    fn stress(&self, syllables: &[Syllable]) -> usize {
+        let corpus = self
+            .heavy_positions
+            .get(syllables.len().wrapping_sub(1))
+            .map_or(&[][..], Vec::as_slice);
+        syllables
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let seen = corpus.get(i).copied().unwrap_or_default() + 1;
+                (i, seen * if s.heavy { 2 } else { 1 })
+            })
+            .rev()
+            .max_by_key(|(_, score)| *score)
+            .map_or(0, |(i, _)| i)
+    }
+
+    pub fn pronounce(&self, name: &GeneratedName) -> Pronunciation {
+        let syllables = name
+            .elements
+            .iter()
+            .filter(|e| e.segment_kind != SegmentKind::Apostrophe)
+            .flat_map(|e| self.phonemes.syllabify(e.written()))
+            .enumerate()
+            .map(|(i, s)| self.syllable(&s, i == 0))
+            .collect::<Vec<_>>();
+        let stress = self.stress(&syllables);
+        let respelling = syllables
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                if i == stress {
+                    s.respelling.to_uppercase()
+                } else {
+                    s.respelling.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("-");
+        let ipa = syllables
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                if i == stress && syllables.len() > 1 {
+                    format!("ˈ{}", s.ipa)
+                } else {
+                    s.ipa.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(".");
+        Pronunciation {
+            respelling,
+            ipa: format!("/{ipa}/"),
+        }
+    }
+}
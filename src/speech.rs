@@ -0,0 +1,325 @@
+use crate::pronunciation::Pronunciation;
+use std::f32::consts::PI;
+use std::path::Path;
+
+pub const SAMPLE_RATE: u32 = 22_050;
+
+/// Seconds of a short vowel, stressed vowels are half as long again.
+const VOWEL_SECONDS: f32 = 0.13;
+/// Pitch of the voice in Hz, raised on the stressed syllable.
+const PITCH: f32 = 110.;
+const STRESSED_PITCH: f32 = 135.;
+/// Silence before and after the name.
+const PAUSE_SECONDS: f32 = 0.1;
+
+/// IPA symbols of more than one character, matched before single characters.
+const CLUSTERS: [&str; 6] = ["tʃ", "dʒ", "aɪ", "aʊ", "eɪ", "ɔɪ"];
+
+/// First three formants of the vowels in Hz.
+const VOWEL_FORMANTS: [(char, [f32; 3]); 23] = [
+    ('ɑ', [730., 1090., 2440.]),
+    ('a', [800., 1300., 2500.]),
+    ('ɛ', [530., 1840., 2480.]),
+    ('e', [400., 2100., 2700.]),
+    ('ɪ', [390., 1990., 2550.]),
+    ('i', [270., 2290., 3010.]),
+    ('ɔ', [570., 840., 2410.]),
+    ('o', [450., 800., 2830.]),
+    ('ʊ', [440., 1020., 2240.]),
+    ('u', [300., 870., 2240.]),
+    ('y', [270., 2100., 2700.]),
+    ('æ', [660., 1720., 2410.]),
+    ('œ', [500., 1500., 2400.]),
+    ('ø', [400., 1600., 2400.]),
+    ('à', [800., 1300., 2500.]),
+    ('á', [800., 1300., 2500.]),
+    ('â', [800., 1300., 2500.]),
+    ('è', [530., 1840., 2480.]),
+    ('é', [400., 2100., 2700.]),
+    ('ê', [530., 1840., 2480.]),
+    ('í', [270., 2290., 3010.]),
+    ('ó', [450., 800., 2830.]),
+    ('ú', [300., 870., 2240.]),
+];
+
+/// The formants of a neutral vocal tract, used where a sound has none of its own.
+const NEUTRAL: [f32; 3] = [500., 1500., 2500.];
+
+/// What the synthesizer produces for a stretch of time.
+#[derive(Copy, Clone, Debug)]
+struct Target {
+    formants: [f32; 3],
+    /// Loudness of the voice, 0 for voiceless sounds.
+    voicing: f32,
+    /// Loudness of the hiss of fricatives and bursts.
+    noise: f32,
+    /// Where the hiss is loudest, in Hz.
+    noise_frequency: f32,
+    seconds: f32,
+    stressed: bool,
+}
+
+impl Target {
+    fn voiced(formants: [f32; 3], voicing: f32, seconds: f32) -> Self {
+        Self {
+            formants,
+            voicing,
+            noise: 0.,
+            noise_frequency: 0.,
+            seconds,
+            stressed: false,
+        }
+    }
+
+    fn hiss(noise: f32, noise_frequency: f32, voicing: f32, seconds: f32) -> Self {
+        Self {
+            formants: NEUTRAL,
+            voicing,
+            noise,
+            noise_frequency,
+            seconds,
+            stressed: false,
+        }
+    }
+
+    fn silence(seconds: f32) -> Self {
+        Self::voiced(NEUTRAL, 0., seconds)
+    }
+}
+
+fn vowel_formants(symbol: char) -> Option<[f32; 3]> {
+    VOWEL_FORMANTS
+        .iter()
+        .find(|(vowel, _)| *vowel == symbol)
+        .map(|(_, formants)| *formants)
+}
+
+/// A closure followed by a burst of noise, with a faint voice bar for voiced stops.
+fn stop(burst_frequency: f32, voiced: bool) -> [Target; 2] {
+    let voicing = if voiced { 0.15 } else { 0. };
+    [
+        Target::hiss(0., 0., voicing, 0.05),
+        Target::hiss(0.6, burst_frequency, voicing, 0.02),
+    ]
+}
+
+/// The targets of one consonant, empty for symbols the synthesizer does not know.
+fn consonant(symbol: &str) -> Vec<Target> {
+    match symbol {
+        "p" => stop(800., false).to_vec(),
+        "b" => stop(800., true).to_vec(),
+        "t" => stop(4000., false).to_vec(),
+        "d" => stop(4000., true).to_vec(),
+        "k" | "q" | "c" => stop(2000., false).to_vec(),
+        "ɡ" | "g" => stop(2000., true).to_vec(),
+        "tʃ" => vec![Target::silence(0.04), Target::hiss(0.5, 2800., 0., 0.08)],
+        "dʒ" => vec![
+            Target::hiss(0., 0., 0.15, 0.04),
+            Target::hiss(0.3, 2800., 0.3, 0.07),
+        ],
+        "s" => vec![Target::hiss(0.5, 5500., 0., 0.11)],
+        "z" => vec![Target::hiss(0.3, 5500., 0.4, 0.1)],
+        "ʃ" => vec![Target::hiss(0.5, 2800., 0., 0.11)],
+        "ʒ" => vec![Target::hiss(0.3, 2800., 0.4, 0.1)],
+        "f" => vec![Target::hiss(0.25, 7000., 0., 0.1)],
+        "v" => vec![Target::hiss(0.15, 7000., 0.4, 0.08)],
+        "θ" => vec![Target::hiss(0.2, 6500., 0., 0.1)],
+        "x" => vec![Target::hiss(0.4, 1600., 0., 0.1)],
+        "ɣ" => vec![Target::hiss(0.2, 1600., 0.4, 0.08)],
+        "h" => vec![Target::hiss(0.3, 1500., 0., 0.07)],
+        "m" => vec![Target::voiced([250., 1100., 2300.], 0.4, 0.08)],
+        "n" => vec![Target::voiced([250., 1700., 2500.], 0.4, 0.08)],
+        "ŋ" => vec![Target::voiced([250., 2000., 2700.], 0.4, 0.08)],
+        "l" => vec![Target::voiced([360., 1300., 2700.], 0.6, 0.07)],
+        "r" => vec![Target::voiced([420., 1300., 1600.], 0.6, 0.07)],
+        "w" => vec![Target::voiced([300., 700., 2200.], 0.6, 0.06)],
+        "j" => vec![Target::voiced([280., 2250., 3000.], 0.6, 0.06)],
+        _ => vec![],
+    }
+}
+
+/// The IPA symbols of one syllable, with whether a vowel is long.
+fn symbols(syllable: &str) -> Vec<(String, bool)> {
+    let mut symbols: Vec<(String, bool)> = vec![];
+    let mut rest = syllable;
+    while let Some(c) = rest.chars().next() {
+        if c == 'ː' {
+            if let Some((_, long)) = symbols.last_mut() {
+                *long = true;
+            }
+            rest = rest.get(c.len_utf8()..).unwrap_or_default();
+            continue;
+        }
+        let symbol = CLUSTERS
+            .iter()
+            .find(|cluster| rest.starts_with(*cluster))
+            .map_or_else(|| c.to_string(), |cluster| (*cluster).to_owned());
+        rest = rest.get(symbol.len()..).unwrap_or_default();
+        symbols.push((symbol, false));
+    }
+    symbols
+}
+
+/// The targets of a whole pronunciation, syllable by syllable.
+fn targets(pronunciation: &Pronunciation) -> Vec<Target> {
+    let mut targets = vec![Target::silence(PAUSE_SECONDS)];
+    let ipa = pronunciation.ipa.trim_matches('/');
+    for syllable in ipa.split('.') {
+        let (syllable, stressed) = match syllable.strip_prefix('ˈ') {
+            Some(syllable) => (syllable, true),
+            None => (syllable, false),
+        };
+        for (symbol, long) in symbols(syllable) {
+            let mut seconds = VOWEL_SECONDS;
+            if stressed {
+                seconds *= 1.5;
+            }
+            if long {
+                seconds *= 1.6;
+            }
+            let mut vowels = symbol
+                .chars()
+                .filter_map(vowel_formants)
+                .collect::<Vec<_>>();
+            // A diphthong glides from its first vowel to its second.
+            if vowels.len() == 2 {
+                seconds *= 1.3;
+            }
+            let mut sounds = if vowels.is_empty() {
+                consonant(&symbol)
+            } else {
+                let share = seconds / vowels.len() as f32;
+                vowels
+                    .drain(..)
+                    .map(|formants| Target::voiced(formants, 1., share))
+                    .collect()
+            };
+            for sound in &mut sounds {
+                sound.stressed = stressed;
+            }
+            targets.extend(sounds);
+        }
+    }
+    targets.push(Target::silence(PAUSE_SECONDS));
+    targets
+}
+
+/// A two-pole resonator that passes frequencies around its center.
+#[derive(Copy, Clone, Debug, Default)]
+struct Resonator {
+    previous: [f32; 2],
+}
+
+impl Resonator {
+    fn process(&mut self, input: f32, frequency: f32, bandwidth: f32) -> f32 {
+        let period = 1. / SAMPLE_RATE as f32;
+        let c = -(-2. * PI * bandwidth * period).exp();
+        let b = 2. * (-PI * bandwidth * period).exp() * (2. * PI * frequency * period).cos();
+        let a = 1. - b - c;
+        let [last, before] = self.previous;
+        let output = a * input + b * last + c * before;
+        self.previous = [output, last];
+        output
+    }
+}
+
+/// Moves `current` a step towards `target` so that sounds blend instead of clicking.
+fn approach(current: f32, target: f32, rate: f32) -> f32 {
+    current + (target - current) * rate
+}
+
+/// Renders a pronunciation as mono samples between -1 and 1 at [`SAMPLE_RATE`].
+///
+/// A glottal pulse train is shaped by three formant resonators and mixed with filtered
+/// noise for fricatives and bursts. The noise comes from a fixed seed, so a name always
+/// sounds the same.
+pub fn synthesize(pronunciation: &Pronunciation) -> Vec<f32> {
+    let targets = targets(pronunciation);
+    let total = targets.iter().map(|t| t.seconds).sum::<f32>();
+    let mut samples = vec![];
+    let mut formants = NEUTRAL;
+    let (mut voicing, mut noise, mut noise_frequency) = (0_f32, 0_f32, 1000_f32);
+    let mut pitch = PITCH;
+    let mut phase = 0_f32;
+    let mut seed = 0x2545_f491_u32;
+    let mut resonators = [Resonator::default(); 3];
+    let mut hiss = Resonator::default();
+    let mut elapsed = 0_f32;
+    for target in &targets {
+        let count = (target.seconds * SAMPLE_RATE as f32) as usize;
+        for _ in 0..count {
+            elapsed += 1. / SAMPLE_RATE as f32;
+            for (current, goal) in formants.iter_mut().zip(target.formants) {
+                *current = approach(*current, goal, 0.004);
+            }
+            voicing = approach(voicing, target.voicing, 0.01);
+            noise = approach(noise, target.noise, 0.02);
+            // Silent noise keeps its color, a resonator tuned near 0 Hz would hum.
+            if target.noise > 0. {
+                noise_frequency = approach(noise_frequency, target.noise_frequency, 0.05);
+            }
+            // The voice falls slightly towards the end of the name.
+            let goal = if target.stressed {
+                STRESSED_PITCH
+            } else {
+                PITCH
+            };
+            pitch = approach(pitch, goal * (1. - 0.15 * elapsed / total), 0.002);
+
+            phase = (phase + pitch / SAMPLE_RATE as f32).fract();
+            let pulse = if phase < 0.6 {
+                (PI * phase / 0.6).sin().powi(2) - 0.3
+            } else {
+                -0.3
+            };
+            let mut voice = pulse * voicing;
+            for (resonator, (frequency, bandwidth)) in resonators
+                .iter_mut()
+                .zip(formants.iter().zip([80., 100., 150.]))
+            {
+                voice = resonator.process(voice, *frequency, bandwidth);
+            }
+
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            let white = seed as f32 / u32::MAX as f32 * 2. - 1.;
+            let hissed = hiss.process(white * noise, noise_frequency, noise_frequency / 3.);
+            samples.push(voice + hissed);
+        }
+    }
+    let peak = samples.iter().fold(0_f32, |peak, s| peak.max(s.abs()));
+    if peak > 0. {
+        for sample in &mut samples {
+            *sample *= 0.8 / peak;
+        }
+    }
+    samples
+}
+
+/// Writes the synthesized pronunciation to a 16-bit mono WAV file.
+///
+/// # Errors
+/// Fails if the file cannot be created or written.
+pub fn write_wav(pronunciation: &Pronunciation, path: &Path) -> Result<(), hound::Error> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for sample in synthesize(pronunciation) {
+        writer.write_sample((sample * f32::from(i16::MAX)) as i16)?;
+    }
+    writer.finalize()
+}
+
+/// A file name for the recording of a name, like `Zul_jin.wav`.
+pub fn wav_file_name(name: &str) -> String {
+    let stem = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    format!("{stem}.wav")
+}
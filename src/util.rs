@@ -1,4 +1,4 @@
-pub const VOWELS: [char; 5] = ['a', 'e', 'i', 'o', 'u'];
+use crate::phonemes::PhonemeRules;
 
 pub fn capitalize(s: &str) -> String {
     let mut c = s.chars();
@@ -8,17 +8,27 @@ pub fn capitalize(s: &str) -> String {
     }
 }
 
-pub fn starts_with_consonant(str: &str) -> bool {
-    if let Some(c) = str.chars().next() {
-        !VOWELS.contains(&c)
-    } else {
-        false
-    }
+/// Splits a single word into rough syllables with the default [`PhonemeRules`], for
+/// code that has no culture pack at hand.
+pub fn syllabify(word: &str) -> Vec<String> {
+    PhonemeRules::default().syllabify(word)
 }
-pub fn ends_with_consonant(str: &str) -> bool {
-    if let Some(c) = str.chars().last() {
-        !VOWELS.contains(&c)
-    } else {
-        false
+
+/// Levenshtein distance between two strings, counted in chars.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for ((cb, diagonal), up) in b.iter().zip(&previous).zip(previous.iter().skip(1)) {
+            let left = current.last().copied().unwrap_or_default();
+            current.push(
+                (diagonal + usize::from(ca != *cb))
+                    .min(up + 1)
+                    .min(left + 1),
+            );
+        }
+        previous = current;
     }
+    previous.last().copied().unwrap_or_default()
 }
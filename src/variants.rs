@@ -0,0 +1,378 @@
+use crate::config::GeneratorConfig;
+use crate::constraints::{ATTEMPTS_PER_NAME, ConstraintError};
+use crate::culture::CulturePack;
+use crate::data_processing::{NameSegment, SegmentKind};
+use crate::joiners::JoinerStyle;
+use crate::name_gen::{GeneratedName, NameGenOptions};
+use crate::util::syllabify;
+use rand::Rng;
+use rand::distr::Distribution as _;
+use rand::distr::weighted::WeightedIndex;
+use rand::seq::{IndexedMutRandom as _, IndexedRandom as _};
+
+/// A change that turns a seed name into a variant.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mutation {
+    /// The first or last part is replaced by one seen in the same position.
+    SwapPart,
+    InsertSyllable,
+    DropSyllable,
+    /// The joiner moves to another boundary between segments.
+    MoveJoiner,
+    /// One vowel is replaced by another.
+    ChangeVowel,
+}
+
+impl Mutation {
+    /// Every mutation with how often it is tried.
+    pub const WEIGHTED: [(Self, f32); 5] = [
+        (Self::SwapPart, 4.),
+        (Self::InsertSyllable, 2.),
+        (Self::DropSyllable, 2.),
+        (Self::MoveJoiner, 1.),
+        (Self::ChangeVowel, 1.5),
+    ];
+
+    pub fn text(self) -> &'static str {
+        match self {
+            Self::SwapPart => "swapped a part",
+            Self::InsertSyllable => "inserted a syllable",
+            Self::DropSyllable => "dropped a syllable",
+            Self::MoveJoiner => "moved the joiner",
+            Self::ChangeVowel => "changed a vowel",
+        }
+    }
+}
+
+pub struct Variant {
+    pub name: GeneratedName,
+    pub mutation: Mutation,
+}
+
+pub struct Variants {
+    /// The seed split into corpus segments.
+    pub original: GeneratedName,
+    pub variants: Vec<Variant>,
+}
+
+/// Splits a name at its joiners and matches the pieces against the corpus: whole pieces
+/// against parts, otherwise their syllables against syllables. Unknown pieces are kept
+/// with neutral statistics.
+pub fn decompose(
+    seed: &str,
+    parts: &[NameSegment],
+    syllables: &[NameSegment],
+    config: &GeneratorConfig,
+) -> GeneratedName {
+    let lower = seed.trim().to_lowercase();
+    let find = |pool: &[NameSegment], text: &str| pool.iter().find(|s| s.str == text).cloned();
+    let mut elements = vec![];
+    let mut chars = lower.chars().peekable();
+    while let Some(c) = chars.peek().copied() {
+        let letters = c.is_alphabetic();
+        let mut run = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_alphabetic() == letters) {
+            run.push(c);
+        }
+        if !letters {
+            if !elements.is_empty() && chars.peek().is_some() {
+                elements.push(NameSegment::joiner(&run));
+            }
+        } else if let Some(part) = find(parts, &run) {
+            elements.push(part);
+        } else {
+            for syllable in syllabify(&run) {
+                elements.push(
+                    find(syllables, &syllable)
+                        .unwrap_or_else(|| NameSegment::unknown(SegmentKind::Syllable, &syllable)),
+                );
+            }
+        }
+    }
+    GeneratedName::from_elements(elements, config)
+        .with_style(JoinerStyle::of(seed).unwrap_or_default())
+}
+
+/// Weighted pick from a pool, preferring segments close to the gender of `like`.
+fn similar<'a>(
+    pool: &[&'a NameSegment],
+    like: &NameSegment,
+    position: impl Fn(&NameSegment) -> f32,
+    rng: &mut impl Rng,
+) -> Option<&'a NameSegment> {
+    let candidates = pool
+        .iter()
+        .filter(|s| s.str != like.str && position(s) > 0.)
+        .copied()
+        .collect::<Vec<_>>();
+    let weights = candidates
+        .iter()
+        .map(|s| position(s) * (1. - (s.gender_ratio - like.gender_ratio).abs()).max(0.01));
+    let index = WeightedIndex::new(weights).ok()?.sample(rng);
+    candidates.get(index).copied()
+}
+
+fn is_joiner(segment: &NameSegment) -> bool {
+    segment.segment_kind == SegmentKind::Apostrophe
+}
+
+/// Applies one mutation, `None` if it does not fit the name.
+fn mutate(
+    elements: &[NameSegment],
+    mutation: Mutation,
+    pools: &Pools<'_>,
+    vowels: &[char],
+    rng: &mut impl Rng,
+) -> Option<Vec<NameSegment>> {
+    let mut elements = elements.to_vec();
+    let last = elements.len().checked_sub(1)?;
+    // Positions strictly between the first and the last segment.
+    let inner = (1..last)
+        .filter(|i| elements.get(*i).is_some_and(|e| !is_joiner(e)))
+        .collect::<Vec<_>>();
+    match mutation {
+        Mutation::SwapPart => {
+            let (index, position): (usize, fn(&NameSegment) -> f32) = if rng.random::<bool>() {
+                (0, |s| s.positional_data.start)
+            } else {
+                (last, |s| s.positional_data.end)
+            };
+            let replaced = elements.get_mut(index)?;
+            *replaced = similar(&pools.parts, replaced, position, rng)?.clone();
+        }
+        Mutation::InsertSyllable => {
+            let like = elements.first()?;
+            let syllable = similar(&pools.middle, like, |s| s.positional_data.middle, rng)?;
+            elements.insert(rng.random_range(1..=last.max(1)), syllable.clone());
+        }
+        Mutation::DropSyllable => {
+            elements.remove(*inner.choose(rng)?);
+        }
+        Mutation::MoveJoiner => {
+            let from = elements.iter().position(is_joiner)?;
+            let joiner = elements.remove(from);
+            let to = (1..elements.len())
+                .filter(|i| *i != from)
+                .collect::<Vec<_>>();
+            elements.insert(*to.choose(rng)?, joiner);
+        }
+        Mutation::ChangeVowel => {
+            let mut segments = elements
+                .iter_mut()
+                .filter(|e| !is_joiner(e))
+                .collect::<Vec<_>>();
+            let segment = segments.choose_mut(rng)?;
+            let positions = segment
+                .str
+                .char_indices()
+                .filter(|(_, c)| vowels.contains(c))
+                .collect::<Vec<_>>();
+            let (at, old) = *positions.choose(rng)?;
+            let new = vowels.iter().filter(|v| **v != old).collect::<Vec<_>>();
+            let new = **new.choose(rng)?;
+            segment
+                .str
+                .replace_range(at..at + old.len_utf8(), new.encode_utf8(&mut [0; 4]));
+        }
+    }
+    Some(elements)
+}
+
+struct Pools<'a> {
+    parts: Vec<&'a NameSegment>,
+    middle: Vec<&'a NameSegment>,
+}
+
+/// Generates `settings.amount` distinct variants of `seed`, each one mutation away from it.
+///
+/// # Errors
+/// Fails if the seed has no letters or the deny-list is invalid.
+pub fn generate_variants(
+    seed: &str,
+    parts: &[NameSegment],
+    syllables: &[NameSegment],
+    settings: &NameGenOptions,
+    pack: &CulturePack,
+    config: &GeneratorConfig,
+) -> Result<Variants, ConstraintError> {
+    let original = decompose(seed, parts, syllables, config);
+    if original.elements.is_empty() {
+        return Err(ConstraintError::Unsatisfiable(
+            "the seed name has no letters".to_owned(),
+        ));
+    }
+    let deny_filter = settings.deny_list.filter()?;
+    let pools = Pools {
+        parts: parts.iter().collect(),
+        middle: syllables
+            .iter()
+            .filter(|s| s.positional_data.middle > 0.)
+            .collect(),
+    };
+    // Only vowels the corpus uses, the pack may list accented ones it never writes.
+    let vowels = pack
+        .phonemes
+        .vowel_letters()
+        .into_iter()
+        .filter(|v| syllables.iter().any(|s| s.str.contains(*v)))
+        .collect::<Vec<_>>();
+    let mut rng = rand::rng();
+    let mut seen = vec![original.to_string().to_lowercase()];
+    let mut variants = vec![];
+    for _ in 0..settings.amount * ATTEMPTS_PER_NAME {
+        if variants.len() >= settings.amount {
+            break;
+        }
+        let Ok((mutation, _)) = Mutation::WEIGHTED.choose_weighted(&mut rng, |(_, w)| *w) else {
+            continue;
+        };
+        let Some(elements) = mutate(&original.elements, *mutation, &pools, &vowels, &mut rng)
+        else {
+            continue;
+        };
+        let name = GeneratedName::from_elements(elements, config).with_style(original.style);
+        let text = name.to_string().to_lowercase();
+        if seen.contains(&text) || deny_filter.denies(&text) {
+            continue;
+        }
+        seen.push(text);
+        variants.push(Variant {
+            name,
+            mutation: *mutation,
+        });
+    }
+    Ok(Variants { original, variants })
+}
+
+/// A blend with the parent each element came from, `None` for joiners.
+pub struct BlendedName {
+    pub name: GeneratedName,
+    pub sources: Vec<Option<usize>>,
+    /// Average log positional count of the segments, higher fits the corpus better.
+    pub score: f32,
+}
+
+impl BlendedName {
+    /// Consecutive segments grouped by the parent they came from, written like in the name.
+    pub fn contributions(&self) -> Vec<(String, usize)> {
+        let mut groups: Vec<(String, usize)> = vec![];
+        for (element, source) in self.name.styled_elements().into_iter().zip(&self.sources) {
+            let Some(source) = *source else {
+                continue;
+            };
+            match groups.last_mut() {
+                Some((text, parent)) if *parent == source => text.push_str(&element),
+                _ => groups.push((element, source)),
+            }
+        }
+        groups
+    }
+}
+
+pub struct Blend {
+    /// The given names split into corpus segments.
+    pub parents: Vec<GeneratedName>,
+    /// The best blends, best first.
+    pub blends: Vec<BlendedName>,
+}
+
+/// Positions between two segments where a name may be cut, joiners stay with the
+/// segment before them.
+fn boundaries(elements: &[NameSegment]) -> Vec<usize> {
+    (1..elements.len())
+        .filter(|i| elements.get(*i).is_some_and(|e| !is_joiner(e)))
+        .collect()
+}
+
+fn score(elements: &[NameSegment], parent_length: f32) -> f32 {
+    let segments = elements
+        .iter()
+        .filter(|e| !is_joiner(e))
+        .collect::<Vec<_>>();
+    let last = segments.len().saturating_sub(1);
+    let fit = segments
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let data = &s.positional_data;
+            let value = match i {
+                0 => data.start,
+                _ if i == last => data.end,
+                _ => data.middle,
+            };
+            value.ln_1p()
+        })
+        .sum::<f32>()
+        / segments.len().max(1) as f32;
+    fit - 0.2 * (segments.len() as f32 - parent_length).abs()
+}
+
+/// Recombines the start of one name with the end of another, for every ordered pair of
+/// names and every cut between their segments, and keeps the `settings.amount` blends
+/// the corpus model scores best.
+///
+/// # Errors
+/// Fails if fewer than two of the names have letters or the deny-list is invalid.
+pub fn blend_names(
+    names: &[&str],
+    parts: &[NameSegment],
+    syllables: &[NameSegment],
+    settings: &NameGenOptions,
+    config: &GeneratorConfig,
+) -> Result<Blend, ConstraintError> {
+    let parents = names
+        .iter()
+        .map(|n| decompose(n, parts, syllables, config))
+        .filter(|p| !p.elements.is_empty())
+        .collect::<Vec<_>>();
+    if parents.len() < 2 {
+        return Err(ConstraintError::Unsatisfiable(
+            "blending needs at least two names".to_owned(),
+        ));
+    }
+    let deny_filter = settings.deny_list.filter()?;
+    let parent_length = parents
+        .iter()
+        .map(|p| p.elements.iter().filter(|e| !is_joiner(e)).count() as f32)
+        .sum::<f32>()
+        / parents.len() as f32;
+    let mut seen = parents
+        .iter()
+        .map(|p| p.to_string().to_lowercase())
+        .collect::<Vec<_>>();
+    let mut blends = vec![];
+    for (a, head) in parents.iter().enumerate() {
+        for (b, tail) in parents.iter().enumerate().filter(|(b, _)| *b != a) {
+            for cut_head in boundaries(&head.elements) {
+                for cut_tail in boundaries(&tail.elements) {
+                    let (Some(start), Some(end)) =
+                        (head.elements.get(..cut_head), tail.elements.get(cut_tail..))
+                    else {
+                        continue;
+                    };
+                    let sources = start
+                        .iter()
+                        .map(|e| (!is_joiner(e)).then_some(a))
+                        .chain(end.iter().map(|e| (!is_joiner(e)).then_some(b)))
+                        .collect();
+                    let elements = [start, end].concat();
+                    let name =
+                        GeneratedName::from_elements(elements, config).with_style(head.style);
+                    let text = name.to_string().to_lowercase();
+                    if seen.contains(&text) || deny_filter.denies(&text) {
+                        continue;
+                    }
+                    seen.push(text);
+                    blends.push(BlendedName {
+                        score: score(&name.elements, parent_length),
+                        name,
+                        sources,
+                    });
+                }
+            }
+        }
+    }
+    blends.sort_by(|a, b| b.score.total_cmp(&a.score));
+    blends.truncate(settings.amount);
+    Ok(Blend { parents, blends })
+}
@@ -0,0 +1,51 @@
+use trollnames::config::GeneratorConfig;
+use trollnames::culture::CulturePack;
+use trollnames::data_processing::{Corpus, CorpusFilter};
+use trollnames::family::{FamilyKind, FamilyMember, FamilyOptions, generate_family};
+use trollnames::name_gen::{CorpusModels, NameGenOptions};
+
+#[test]
+fn members_write_the_shared_part_alike() {
+    let pack = CulturePack::default();
+    let (corpus, _) = Corpus::load(&pack.name, &pack.corpus, &CorpusFilter::default())
+        .expect("bundled corpus is readable");
+    let models = CorpusModels::learn(&corpus.names, &pack);
+    for kind in FamilyKind::ALL {
+        for _ in 0..20 {
+            let family = generate_family(
+                &corpus.parts,
+                &corpus.syllables,
+                &models,
+                &NameGenOptions::default(),
+                &pack,
+                &GeneratorConfig::default(),
+                &FamilyOptions { kind, size: 4 },
+            )
+            .expect("the defaults generate families");
+            // A lineage passes a part from one member to the next only.
+            for pair in family.members.windows(2) {
+                let [older, younger] = pair else {
+                    continue;
+                };
+                let shared = |member: &FamilyMember, last: bool| {
+                    let index = if last {
+                        member.shared.iter().max()
+                    } else {
+                        member.shared.iter().min()
+                    };
+                    index
+                        .and_then(|i| member.name.elements.get(*i))
+                        .map(|e| e.written().to_owned())
+                };
+                let passed = kind == FamilyKind::Lineage;
+                assert_eq!(
+                    shared(older, passed),
+                    shared(younger, false),
+                    "{} and {}",
+                    older.name,
+                    younger.name
+                );
+            }
+        }
+    }
+}
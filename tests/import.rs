@@ -0,0 +1,78 @@
+use std::collections::HashSet;
+use trollnames::data_processing::{append_records, read_records};
+use trollnames::import::{
+    ColumnMapping, IMPORTED_SOURCE, ImportCandidate, clean_name, existing_names, known_titles,
+    table_candidates, wowhead_candidates,
+};
+
+#[test]
+fn bundled_search_page_adds_no_descriptors() {
+    let corpus = read_records("data/syllables.csv").expect("bundled corpus is readable");
+    let page = std::fs::read_to_string("test.txt").expect("bundled search page is readable");
+    let titles = known_titles(&corpus);
+    let mut existing = existing_names(&corpus);
+    let candidates =
+        wowhead_candidates(&page, &titles, &mut existing).expect("page has an npc listview");
+    assert!(
+        candidates.is_empty(),
+        "unexpected candidates {candidates:?}"
+    );
+}
+
+#[test]
+fn clean_name_keeps_the_proper_name() {
+    let titles = HashSet::from(["ma'da".to_owned()]);
+    for (full, clean) in [
+        ("Vision of Vol'jin", "Vol'jin"),
+        ("Zul'jin of the Amani", "Zul'jin"),
+        ("Amaka the Crawg Ma'da", "Amaka"),
+        ("Vol'jin's Spirit", "Vol'jin"),
+    ] {
+        assert_eq!(clean_name(full, &titles).as_deref(), Some(clean), "{full}");
+    }
+}
+
+#[test]
+fn appended_rows_keep_their_source() {
+    let path = std::env::temp_dir().join(format!("trollnames-append-{}.csv", std::process::id()));
+    let path = path.to_str().expect("temp path is utf-8");
+    std::fs::copy("data/syllables.csv", path).expect("bundled corpus can be copied");
+    let corpus = read_records(path).expect("copied corpus is readable");
+    let table = "Name,Race,Sex\nMaka the Brave,Troll,Female\n";
+    let mapping = ColumnMapping {
+        name: "Name".to_owned(),
+        race: "Race".to_owned(),
+        gender: Some("Sex".to_owned()),
+    };
+    let candidates = table_candidates(
+        table.as_bytes(),
+        &mapping,
+        "troll",
+        &known_titles(&corpus),
+        &mut existing_names(&corpus),
+    )
+    .expect("table is valid");
+    let records = candidates
+        .into_iter()
+        .map(ImportCandidate::into_record)
+        .collect::<Vec<_>>();
+    append_records(path, &records).expect("corpus can be appended to");
+
+    let mut rdr = csv::Reader::from_path(path).expect("appended corpus is readable");
+    let headers = rdr.headers().expect("corpus has a header").clone();
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    let rows = rdr
+        .records()
+        .collect::<Result<Vec<_>, _>>()
+        .expect("every row is valid");
+    std::fs::remove_file(path).expect("temp corpus can be removed");
+    assert_eq!(rows.len(), corpus.len() + 1);
+    let maka = rows
+        .iter()
+        .find(|row| column("clean name").and_then(|i| row.get(i)) == Some("Maka"))
+        .expect("appended row is read back");
+    assert_eq!(
+        column("source").and_then(|i| maka.get(i)),
+        Some(IMPORTED_SOURCE)
+    );
+}
@@ -0,0 +1,24 @@
+use trollnames::culture::CulturePack;
+use trollnames::data_processing::{Corpus, CorpusFilter};
+use trollnames::orthography::Orthography;
+
+fn written(orthography: &Orthography, key: &str) -> Vec<String> {
+    orthography
+        .spellings(key)
+        .iter()
+        .map(|(spelling, _)| spelling.clone())
+        .collect()
+}
+
+#[test]
+fn misaligned_rows_teach_no_spellings() {
+    let pack = CulturePack::default();
+    let (corpus, _) = Corpus::load(&pack.name, &pack.corpus, &CorpusFilter::default())
+        .expect("bundled corpus is readable");
+    let orthography = Orthography::learn(&corpus.names);
+    // "Tasha" is `ra.sha` and "Khar'vaxel" is `ar.va.xel` in the corpus.
+    assert!(!written(&orthography, "ra").contains(&"ta".to_owned()));
+    assert!(!written(&orthography, "ar").contains(&"khar".to_owned()));
+    // "Cazex" is `ka.zex`, a spelling the rows do agree on.
+    assert!(written(&orthography, "ka").contains(&"ca".to_owned()));
+}